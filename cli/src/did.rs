@@ -1,15 +1,60 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    io::{stdin, stdout, BufReader, Read, Write},
+    path::PathBuf,
+    time::Duration,
+};
 
-use didkit::{DIDCreate, DID_METHODS};
+use chrono::{DateTime, Duration as TimeDelta, Utc};
+use didkit::ssi::ssi_dids::{
+    cache::DEFAULT_MAX_ENTRIES,
+    did_configuration::{
+        verify_linked_domains, ConfigurationSource, DIDConfiguration, DomainLinkageCredential,
+        FileConfigurationSource, HTTPConfigurationSource, LinkedDid,
+    },
+    validate::{validate_json, Severity},
+    OneOrMany, Service, ServiceEndpoint, VerificationMethodMap, VerificationRelationship,
+};
+use didkit::{
+    BundleEntry, CachingDIDResolver, DIDCreate, DIDDeactivate, DIDDocumentOperation,
+    DIDMethodTransaction, DIDRecover, DIDResolver, DIDUpdate, Document, Error as DIDError,
+    FileCacheStore, HTTPDIDResolver, ResolutionInputMetadata, ResolutionResult, StaticDIDResolver,
+    DID_METHODS,
+};
 
-use crate::{metadata_properties_to_value, read_jwk_file_opt, MetadataProperty};
+use crate::{
+    metadata_properties_to_value, options_to_value, read_jwk_file, read_jwk_file_opt,
+    MetadataProperty,
+};
 
 #[derive(Subcommand)]
 pub enum DidCmd {
     /// Create new DID Document.
     Create(DidCreateArgs),
+    /// Resolve a DID to a DID Document.
+    Resolve(DidResolveArgs),
+    /// Update a DID's document, producing a DID method transaction.
+    Update(Box<DidUpdateArgs>),
+    /// Recover a DID with its recovery key, replacing its keys and DID Document, producing a DID
+    /// method transaction.
+    Recover(DidRecoverArgs),
+    /// Permanently deactivate a DID with its recovery key, producing a DID method transaction.
+    Deactivate(DidDeactivateArgs),
+    /// Submit a DID method transaction, as output by a DID operation command.
+    SubmitTx(DidSubmitTxArgs),
+    /// Validate a DID Document against DID Core.
+    Validate(DidValidateArgs),
+    /// Export resolution results for DIDs to a bundle directory, for offline resolution.
+    Export(DidExportArgs),
+    /// Issue a Domain Linkage Credential linking a DID to a web origin.
+    LinkDomain(DidLinkDomainArgs),
+    /// Build a DID Configuration Resource (`/.well-known/did-configuration.json`) from Domain
+    /// Linkage Credentials.
+    WellKnown(DidWellKnownArgs),
+    /// Verify the web origins listed in the LinkedDomains services of a DID.
+    VerifyDomains(DidVerifyDomainsArgs),
 }
 
 #[derive(Args)]
@@ -37,11 +82,256 @@ pub struct DidCreateArgs {
     ///
     /// More info: https://identity.foundation/did-registration/#options
     options: Vec<MetadataProperty>,
+    /// JSON or YAML file with options for DID create operation. Options given with `-o` take
+    /// precedence.
+    #[clap(long)]
+    options_file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct DidUpdateArgs {
+    /// DID to update
+    did: String,
+    /// JWK file with the current update key, authorizing the operation
+    #[clap(short, long)]
+    update_key: PathBuf,
+    /// JWK file with the new update key, for the next DID Update operation
+    #[clap(short = 'U', long)]
+    new_update_key: PathBuf,
+    /// Options for DID update operation
+    ///
+    /// More info: https://identity.foundation/did-registration/#options
+    #[clap(short, long)]
+    options: Vec<MetadataProperty>,
+    /// JSON or YAML file with options for DID update operation. Options given with `-o` take
+    /// precedence.
+    #[clap(long)]
+    options_file: Option<PathBuf>,
+    #[clap(subcommand)]
+    operation: DidUpdateCmd,
+}
+
+#[derive(Args)]
+pub struct DidRecoverArgs {
+    /// DID to recover
+    did: String,
+    /// JWK file with the current recovery key, authorizing the operation
+    #[clap(short, long)]
+    recovery_key: PathBuf,
+    /// JWK file with the new update key, for the next DID Update operation
+    #[clap(short = 'U', long)]
+    new_update_key: PathBuf,
+    /// JWK file with the new recovery key, for the next DID Recover or Deactivate operation
+    #[clap(short = 'R', long)]
+    new_recovery_key: PathBuf,
+    /// JWK file for the default verification method of the recovered DID Document. The DID
+    /// Document has no verification methods if omitted.
+    #[clap(short = 'v', long)]
+    new_verification_key: Option<PathBuf>,
+    /// Options for DID recover operation, e.g. `-o anchorOrigin=...`
+    ///
+    /// More info: https://identity.foundation/did-registration/#options
+    #[clap(short, long)]
+    options: Vec<MetadataProperty>,
+    /// JSON or YAML file with options for DID recover operation. Options given with `-o` take
+    /// precedence.
+    #[clap(long)]
+    options_file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct DidDeactivateArgs {
+    /// DID to deactivate
+    did: String,
+    /// JWK file with the current recovery key, authorizing the operation
+    #[clap(short, long)]
+    recovery_key: PathBuf,
+    /// Options for DID deactivate operation
+    ///
+    /// More info: https://identity.foundation/did-registration/#options
+    #[clap(short, long)]
+    options: Vec<MetadataProperty>,
+    /// JSON or YAML file with options for DID deactivate operation. Options given with `-o` take
+    /// precedence.
+    #[clap(long)]
+    options_file: Option<PathBuf>,
+}
+
+/// Change to make to the DID document in a DID update operation
+#[derive(Subcommand)]
+pub enum DidUpdateCmd {
+    /// Add or replace a verification method.
+    SetVerificationMethod {
+        /// Verification method id, e.g. `#key-2`
+        id: String,
+        /// JWK file with the public key of the verification method
+        #[clap(short = 'k', long)]
+        public_key: PathBuf,
+        /// Verification method type
+        #[clap(short = 't', long = "type", default_value = "JsonWebSignature2020")]
+        type_: String,
+        /// Verification relationship for the verification method, e.g. `-p assertionMethod`.
+        /// May be repeated. Defaults to all verification relationships.
+        #[clap(short, long, value_parser = parse_verification_relationship)]
+        purpose: Vec<VerificationRelationship>,
+    },
+    /// Add or replace a service.
+    SetService {
+        /// Service id, e.g. `#linked-domain`
+        id: String,
+        /// Service type, e.g. `LinkedDomains`
+        #[clap(short = 't', long = "type")]
+        type_: String,
+        /// Service endpoint URI, or JSON object
+        #[clap(short, long)]
+        endpoint: String,
+    },
+    /// Remove a verification method.
+    RemoveVerificationMethod {
+        /// Verification method id
+        id: String,
+    },
+    /// Remove a service.
+    RemoveService {
+        /// Service id
+        id: String,
+    },
+    /// Replace the contents of the DID document.
+    SetDidDocument {
+        /// JSON file containing the DID Document. Reads from standard input if omitted.
+        file: Option<PathBuf>,
+    },
+}
+
+fn parse_verification_relationship(s: &str) -> Result<VerificationRelationship, String> {
+    serde_json::from_value(serde_json::Value::String(s.to_string()))
+        .map_err(|_| format!("Unknown verification relationship: {s}"))
+}
+
+#[derive(Args)]
+pub struct DidResolveArgs {
+    /// DID to resolve
+    did: String,
+    /// HTTP(S) endpoint of a DID resolver, e.g. a Universal Resolver, to use instead of the
+    /// built-in DID methods
+    ///
+    /// The built-in DID methods fall back to the resolver set in `DID_RESOLVER`.
+    #[clap(short = 'r', long, conflicts_with = "bundle")]
+    did_resolver: Option<String>,
+    /// Bundle directory, as written by `did export`, to resolve from instead of the network
+    #[clap(long)]
+    bundle: Option<PathBuf>,
+    /// JWK file with the public key that must have signed the bundle manifest
+    #[clap(long, requires = "bundle")]
+    bundle_key: Option<PathBuf>,
+    /// Output the DID resolution result with metadata instead of only the DID document
+    #[clap(short = 'm', long)]
+    with_metadata: bool,
+    /// Resolution input metadata, e.g. `-i noCache`
+    ///
+    /// With `-i accept=<media type>`, outputs that representation of the DID Document:
+    /// `application/did+json`, `application/did+ld+json` or `application/did+cbor`.
+    ///
+    /// More info: https://www.w3.org/TR/did-core/#did-resolution-input-metadata
+    #[clap(short = 'i', long = "input-metadata")]
+    input_metadata: Vec<MetadataProperty>,
+    /// Directory for caching resolution results between invocations
+    #[clap(long, env = "DIDKIT_CACHE_DIR")]
+    cache_dir: Option<PathBuf>,
+    /// Time in seconds for which cached resolution results are used
+    #[clap(long, default_value_t = 300)]
+    cache_ttl: u64,
+}
+
+#[derive(Args)]
+pub struct DidExportArgs {
+    /// DIDs to resolve and export
+    #[clap(required = true)]
+    dids: Vec<String>,
+    /// Bundle directory to write
+    #[clap(short, long)]
+    out: PathBuf,
+    /// HTTP(S) endpoint of a DID resolver to use instead of the built-in DID methods
+    #[clap(short = 'r', long)]
+    did_resolver: Option<String>,
+    /// JWK file with a private key to sign the bundle manifest with
+    #[clap(short = 'k', long)]
+    sign_key: Option<PathBuf>,
+    /// Key ID (`kid`) to set in the bundle manifest signature, e.g. a verification method ID
+    #[clap(long, requires = "sign_key")]
+    key_id: Option<String>,
+}
+
+#[derive(Args)]
+pub struct DidLinkDomainArgs {
+    /// DID to link
+    did: String,
+    /// Web origin to link, e.g. `https://example.com`
+    origin: String,
+    /// JWK file with the private key of the verification method
+    #[clap(short, long)]
+    key: PathBuf,
+    /// Verification method (DID URL) of the key, authorized for `assertionMethod`. Defaults to
+    /// the first such verification method of the resolved DID document with the key.
+    #[clap(short, long)]
+    verification_method: Option<String>,
+    /// Expiration date of the credential (RFC 3339). Defaults to one year from now.
+    #[clap(long)]
+    expiration_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Args)]
+pub struct DidWellKnownArgs {
+    /// Files containing Domain Linkage Credentials, as output by `did link-domain`
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct DidVerifyDomainsArgs {
+    /// DID whose linked domains to verify
+    did: String,
+    /// HTTP(S) endpoint of a DID resolver to use instead of the built-in DID methods
+    #[clap(short = 'r', long)]
+    did_resolver: Option<String>,
+    /// Read the DID Configuration Resource of an origin from a local file instead of fetching
+    /// it, given as `ORIGIN=PATH`. May be repeated.
+    #[clap(long, value_parser = parse_origin_file)]
+    config_file: Vec<(String, PathBuf)>,
+}
+
+fn parse_origin_file(s: &str) -> Result<(String, PathBuf), String> {
+    match s.split_once('=') {
+        Some((origin, path)) => Ok((origin.to_string(), PathBuf::from(path))),
+        None => Err("Expected ORIGIN=PATH".to_string()),
+    }
+}
+
+#[derive(Args)]
+pub struct DidSubmitTxArgs {
+    /// JSON file containing the transaction. Reads from standard input if omitted.
+    file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct DidValidateArgs {
+    /// JSON file containing the DID Document. Reads from standard input if omitted.
+    file: Option<PathBuf>,
 }
 
 pub async fn cli(cmd: DidCmd) -> Result<()> {
     match cmd {
         DidCmd::Create(a) => create(a).await?,
+        DidCmd::Resolve(a) => resolve(a).await?,
+        DidCmd::Update(a) => update(*a).await?,
+        DidCmd::Recover(a) => recover(a).await?,
+        DidCmd::Deactivate(a) => deactivate(a).await?,
+        DidCmd::SubmitTx(a) => submit_tx(a).await?,
+        DidCmd::Validate(a) => validate(a).await?,
+        DidCmd::Export(a) => export(a).await?,
+        DidCmd::LinkDomain(a) => link_domain(a).await?,
+        DidCmd::WellKnown(a) => well_known(a).await?,
+        DidCmd::VerifyDomains(a) => verify_domains(a).await?,
     };
     Ok(())
 }
@@ -49,20 +339,20 @@ pub async fn cli(cmd: DidCmd) -> Result<()> {
 pub async fn create(args: DidCreateArgs) -> Result<()> {
     let method = DID_METHODS
         .get(&args.method)
-        .ok_or(anyhow!("Unable to get DID method"))?;
+        .ok_or_else(|| DIDError::MethodNotSupported(args.method.clone()))?;
     let update_key =
         read_jwk_file_opt(&args.update_key).context("Read update key for DID Create")?;
     let verification_key = read_jwk_file_opt(&args.verification_key)
         .context("Read verification key for DID Create")?;
     let recovery_key =
         read_jwk_file_opt(&args.recovery_key).context("Read recovery key for DID Create")?;
-    let options =
-        metadata_properties_to_value(args.options).context("Parse options for DID Create")?;
+    let options = options_to_value(&args.options_file, args.options)
+        .context("Parse options for DID Create")?;
     let options = serde_json::from_value(options).context("Unable to convert options")?;
 
     let tx = method
         .create(DIDCreate {
-            options: options,
+            options,
             update_key,
             recovery_key,
             verification_key,
@@ -72,3 +362,352 @@ pub async fn create(args: DidCreateArgs) -> Result<()> {
     println!("{}", serde_json::to_string_pretty(&tx).unwrap());
     Ok(())
 }
+
+pub async fn update(args: DidUpdateArgs) -> Result<()> {
+    let method = DID_METHODS.get_method(&args.did)?;
+    let update_key = read_jwk_file(&args.update_key).context("Read update key for DID Update")?;
+    let new_update_key =
+        read_jwk_file(&args.new_update_key).context("Read new update key for DID Update")?;
+    let options = options_to_value(&args.options_file, args.options)
+        .context("Parse options for DID Update")?;
+    let options = serde_json::from_value(options).context("Unable to convert options")?;
+    let operation = match args.operation {
+        DidUpdateCmd::SetVerificationMethod {
+            id,
+            public_key,
+            type_,
+            purpose,
+        } => {
+            let jwk = read_jwk_file(&public_key).context("Read verification method key")?;
+            let vmm = VerificationMethodMap {
+                id,
+                type_,
+                controller: args.did.clone(),
+                public_key_jwk: Some(jwk.to_public()),
+                public_key_multibase: None,
+                property_set: None,
+            };
+            let purposes = if purpose.is_empty() {
+                VerificationRelationship::ALL.to_vec()
+            } else {
+                purpose
+            };
+            DIDDocumentOperation::SetVerificationMethod { vmm, purposes }
+        }
+        DidUpdateCmd::SetService {
+            id,
+            type_,
+            endpoint,
+        } => {
+            let endpoint = if endpoint.starts_with('{') {
+                let value = serde_json::from_str(&endpoint).context("Parse service endpoint")?;
+                ServiceEndpoint::Map(value)
+            } else {
+                ServiceEndpoint::URI(endpoint)
+            };
+            DIDDocumentOperation::SetService(Service {
+                id,
+                type_: OneOrMany::One(type_),
+                service_endpoint: Some(OneOrMany::One(endpoint)),
+                property_set: None,
+            })
+        }
+        DidUpdateCmd::RemoveVerificationMethod { id } => {
+            DIDDocumentOperation::RemoveVerificationMethod(id)
+        }
+        DidUpdateCmd::RemoveService { id } => DIDDocumentOperation::RemoveService(id),
+        DidUpdateCmd::SetDidDocument { file } => {
+            let doc: Document = match file {
+                Some(path) => {
+                    let file = File::open(path).context("Open DID Document file")?;
+                    serde_json::from_reader(BufReader::new(file))
+                        .context("Read DID Document file")?
+                }
+                None => serde_json::from_reader(stdin()).context("Read DID Document from stdin")?,
+            };
+            DIDDocumentOperation::SetDidDocument(doc)
+        }
+    };
+
+    let tx = method
+        .update(DIDUpdate {
+            did: args.did,
+            update_key: Some(update_key),
+            new_update_key: Some(new_update_key),
+            operation,
+            options,
+        })
+        .context("DID Update failed")?;
+
+    println!("{}", serde_json::to_string_pretty(&tx).unwrap());
+    Ok(())
+}
+
+pub async fn recover(args: DidRecoverArgs) -> Result<()> {
+    let method = DID_METHODS.get_method(&args.did)?;
+    let recovery_key =
+        read_jwk_file(&args.recovery_key).context("Read recovery key for DID Recover")?;
+    let new_update_key =
+        read_jwk_file(&args.new_update_key).context("Read new update key for DID Recover")?;
+    let new_recovery_key =
+        read_jwk_file(&args.new_recovery_key).context("Read new recovery key for DID Recover")?;
+    let new_verification_key = read_jwk_file_opt(&args.new_verification_key)
+        .context("Read new verification key for DID Recover")?;
+    let options = options_to_value(&args.options_file, args.options)
+        .context("Parse options for DID Recover")?;
+    let options = serde_json::from_value(options).context("Unable to convert options")?;
+
+    let tx = method
+        .recover(DIDRecover {
+            did: args.did,
+            recovery_key: Some(recovery_key),
+            new_update_key: Some(new_update_key),
+            new_recovery_key: Some(new_recovery_key),
+            new_verification_key,
+            options,
+        })
+        .context("DID Recover failed")?;
+
+    println!("{}", serde_json::to_string_pretty(&tx).unwrap());
+    Ok(())
+}
+
+pub async fn deactivate(args: DidDeactivateArgs) -> Result<()> {
+    let method = DID_METHODS.get_method(&args.did)?;
+    let recovery_key =
+        read_jwk_file(&args.recovery_key).context("Read recovery key for DID Deactivate")?;
+    let options = options_to_value(&args.options_file, args.options)
+        .context("Parse options for DID Deactivate")?;
+    let options = serde_json::from_value(options).context("Unable to convert options")?;
+
+    let tx = method
+        .deactivate(DIDDeactivate {
+            did: args.did,
+            key: Some(recovery_key),
+            options,
+        })
+        .context("DID Deactivate failed")?;
+
+    println!("{}", serde_json::to_string_pretty(&tx).unwrap());
+    Ok(())
+}
+
+pub async fn resolve(args: DidResolveArgs) -> Result<()> {
+    let input_metadata = metadata_properties_to_value(args.input_metadata)
+        .context("Parse input metadata for DID Resolve")?;
+    let input_metadata: ResolutionInputMetadata =
+        serde_json::from_value(input_metadata).context("Unable to convert input metadata")?;
+
+    let http_resolver = args.did_resolver.as_deref().map(HTTPDIDResolver::new);
+    let bundle_key = read_jwk_file_opt(&args.bundle_key).context("Read bundle key")?;
+    let static_resolver = match (&args.bundle, &bundle_key) {
+        (Some(dir), Some(key)) => {
+            Some(StaticDIDResolver::from_dir_verified(dir, key).context("Load signed bundle")?)
+        }
+        (Some(dir), None) => Some(StaticDIDResolver::from_dir(dir).context("Load bundle")?),
+        (None, _) => None,
+    };
+    let resolver: &dyn DIDResolver = match (&http_resolver, &static_resolver) {
+        (Some(http_resolver), _) => http_resolver,
+        (None, Some(static_resolver)) => static_resolver,
+        (None, None) => &*DID_METHODS,
+    };
+    let cache_ttl = Duration::from_secs(args.cache_ttl);
+    let caching_resolver = args.cache_dir.map(|cache_dir| {
+        CachingDIDResolver::new(resolver, DEFAULT_MAX_ENTRIES, cache_ttl)
+            .with_store(FileCacheStore::new(cache_dir))
+    });
+    let resolver: &dyn DIDResolver = match &caching_resolver {
+        Some(caching_resolver) => caching_resolver,
+        None => resolver,
+    };
+
+    if input_metadata.accept.is_some() && !args.with_metadata {
+        let (res_meta, bytes, _) = resolver
+            .resolve_representation(&args.did, &input_metadata)
+            .await;
+        if let Some(code) = res_meta.error {
+            return Err(DIDError::from_code(code, args.did).into());
+        }
+        stdout()
+            .write_all(&bytes)
+            .context("Write DID Document representation")?;
+        return Ok(());
+    }
+
+    let (res_meta, doc_opt, doc_meta_opt) = resolver.resolve(&args.did, &input_metadata).await;
+
+    if args.with_metadata {
+        let result = ResolutionResult::new(res_meta.clone(), doc_opt, doc_meta_opt);
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+    } else if let Some(doc) = doc_opt {
+        println!("{}", serde_json::to_string_pretty(&doc).unwrap());
+    }
+    if let Some(code) = res_meta.error {
+        return Err(DIDError::from_code(code, args.did).into());
+    }
+    Ok(())
+}
+
+pub async fn export(args: DidExportArgs) -> Result<()> {
+    let sign_key = read_jwk_file_opt(&args.sign_key).context("Read bundle signing key")?;
+    let http_resolver = args.did_resolver.as_deref().map(HTTPDIDResolver::new);
+    let resolver: &dyn DIDResolver = match &http_resolver {
+        Some(http_resolver) => http_resolver,
+        None => &*DID_METHODS,
+    };
+
+    let mut bundle = StaticDIDResolver::new();
+    for did in args.dids {
+        let (res_meta, doc_opt, doc_meta_opt) = resolver
+            .resolve(&did, &ResolutionInputMetadata::default())
+            .await;
+        if let Some(code) = res_meta.error {
+            return Err(DIDError::from_code(code, did).into());
+        }
+        let entry = BundleEntry {
+            result: ResolutionResult::new(res_meta, doc_opt, doc_meta_opt),
+            versions: Vec::new(),
+        };
+        bundle.insert(&did, entry);
+    }
+    let manifest = bundle
+        .write_dir(&args.out, sign_key.as_ref(), args.key_id)
+        .context("Write bundle")?;
+
+    println!("{}", serde_json::to_string_pretty(&manifest).unwrap());
+    Ok(())
+}
+
+pub async fn submit_tx(args: DidSubmitTxArgs) -> Result<()> {
+    let tx: DIDMethodTransaction = match args.file {
+        Some(path) => {
+            let file = File::open(path).context("Open transaction file")?;
+            serde_json::from_reader(BufReader::new(file)).context("Read transaction file")?
+        }
+        None => serde_json::from_reader(stdin()).context("Read transaction from stdin")?,
+    };
+    let method = DID_METHODS
+        .get(&tx.did_method)
+        .ok_or_else(|| DIDError::MethodNotSupported(tx.did_method.clone()))?;
+    let result = method
+        .submit_transaction(tx)
+        .await
+        .context("DID transaction submission failed")?;
+
+    println!("{}", serde_json::to_string_pretty(&result).unwrap());
+    Ok(())
+}
+
+pub async fn validate(args: DidValidateArgs) -> Result<()> {
+    let mut json = Vec::new();
+    match args.file {
+        Some(path) => {
+            let mut file = File::open(path).context("Open DID Document file")?;
+            file.read_to_end(&mut json)
+                .context("Read DID Document file")?;
+        }
+        None => {
+            stdin()
+                .read_to_end(&mut json)
+                .context("Read DID Document from stdin")?;
+        }
+    };
+    let findings = validate_json(&json);
+
+    println!("{}", serde_json::to_string_pretty(&findings).unwrap());
+    if findings
+        .iter()
+        .any(|finding| finding.severity == Severity::Error)
+    {
+        return Err(DIDError::Other {
+            code: "invalidDidDocument".to_string(),
+            message: "DID Document is not valid".to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+pub async fn link_domain(args: DidLinkDomainArgs) -> Result<()> {
+    let key = read_jwk_file(&args.key).context("Read key for Domain Linkage Credential")?;
+    let issuance_date = Utc::now();
+    let expiration_date = args
+        .expiration_date
+        .unwrap_or_else(|| issuance_date + TimeDelta::days(365));
+    let credential =
+        DomainLinkageCredential::new(&args.did, &args.origin, issuance_date, expiration_date);
+
+    let jwt = match args.verification_method {
+        Some(verification_method) => credential.to_jwt(&key, &verification_method),
+        None => {
+            let (res_meta, doc_opt, _) = DID_METHODS
+                .resolve(&args.did, &ResolutionInputMetadata::default())
+                .await;
+            if let Some(code) = res_meta.error {
+                return Err(DIDError::from_code(code, args.did).into());
+            }
+            let doc = doc_opt.ok_or_else(|| DIDError::NotFound(args.did.clone()))?;
+            credential.issue_jwt(&doc, &key)
+        }
+    }
+    .context("Sign Domain Linkage Credential")?;
+    println!("{jwt}");
+    Ok(())
+}
+
+pub async fn well_known(args: DidWellKnownArgs) -> Result<()> {
+    let mut linked_dids = Vec::with_capacity(args.files.len());
+    for path in args.files {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Read credential file {}", path.display()))?;
+        // JWT credentials may be given as plain text or as a JSON string.
+        let linked_did = match serde_json::from_str(&content) {
+            Ok(linked_did) => linked_did,
+            Err(_) => LinkedDid::Jwt(content.trim().to_string()),
+        };
+        linked_dids.push(linked_did);
+    }
+    let configuration = DIDConfiguration::new(linked_dids);
+
+    println!("{}", serde_json::to_string_pretty(&configuration).unwrap());
+    Ok(())
+}
+
+pub async fn verify_domains(args: DidVerifyDomainsArgs) -> Result<()> {
+    let http_resolver = args.did_resolver.as_deref().map(HTTPDIDResolver::new);
+    let resolver: &dyn DIDResolver = match &http_resolver {
+        Some(http_resolver) => http_resolver,
+        None => &*DID_METHODS,
+    };
+    let mut file_source = FileConfigurationSource::new();
+    for (origin, path) in &args.config_file {
+        file_source.insert(origin, path);
+    }
+    let source: &dyn ConfigurationSource = if args.config_file.is_empty() {
+        &HTTPConfigurationSource
+    } else {
+        &file_source
+    };
+
+    let verifications = verify_linked_domains(resolver, source, &args.did)
+        .await
+        .context("Verify linked domains")?;
+    let report: Vec<_> = verifications
+        .iter()
+        .map(|verification| match &verification.result {
+            Ok(()) => serde_json::json!({ "origin": verification.origin, "verified": true }),
+            Err(err) => serde_json::json!({
+                "origin": verification.origin,
+                "verified": false,
+                "error": err.to_string(),
+            }),
+        })
+        .collect();
+
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    if verifications.iter().any(|v| v.result.is_err()) {
+        return Err(DIDError::Unauthorized("Not all linked domains verified".to_string()).into());
+    }
+    Ok(())
+}
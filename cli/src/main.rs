@@ -1,9 +1,16 @@
-use std::{fs::File, io::BufReader, path::PathBuf, str::FromStr};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use anyhow::{Context, Error, Result};
 use clap::{Parser, Subcommand};
-use didkit::{ssi::ssi_dids::did_resolve::Metadata, JWK};
-use serde_json::Value;
+use didkit::{
+    ssi::ssi_dids::did_resolve::Metadata, DIDMethodError, Error as DIDError, ErrorCode, JWK,
+};
+use serde_json::{json, Value};
 
 mod did;
 mod key;
@@ -28,15 +35,48 @@ async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
     let opt = DIDKit::parse();
-    match opt.command {
-        DIDKitCmd::Did(args) => did::cli(args).await.unwrap(),
-        DIDKitCmd::Key(args) => key::cli(args).await.unwrap(),
+    let result = match opt.command {
+        DIDKitCmd::Did(args) => did::cli(args).await,
+        DIDKitCmd::Key(args) => key::cli(args).await,
+    };
+    if let Err(err) = result {
+        eprintln!("{}", error_report(&err));
+        std::process::exit(1);
     }
     Ok(())
 }
 
+/// Report a failure in a machine-readable form, using the DID Resolution/Registration error
+/// codes where known.
+fn error_report(err: &Error) -> Value {
+    json!({
+        "error": error_code(err),
+        "message": format!("{err:#}"),
+    })
+}
+
+fn error_code(err: &Error) -> ErrorCode {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<DIDError>() {
+            return err.code();
+        }
+        if let Some(err) = cause.downcast_ref::<DIDMethodError>() {
+            return err.code();
+        }
+    }
+    ErrorCode::InternalError
+}
+
 #[derive(Clone)]
-/// Subset of [DID Metadata Structure][metadata] that is just a string property name and string value.
+/// A [DID Metadata Structure][metadata] property given on the command line.
+///
+/// Accepted forms:
+/// - `name=value`: string value
+/// - `name:json=value`: JSON literal value, e.g. a number, list or map
+/// - `name`: boolean `true`
+///
+/// The name may be a dotted path such as `a.b`, which sets property `b` in map property `a`.
+///
 /// [metadata]: https://w3c.github.io/did-core/#metadata-structure
 pub struct MetadataProperty {
     pub name: String,
@@ -48,26 +88,52 @@ impl FromStr for MetadataProperty {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parts = s.splitn(2, '=');
-        let name = parts.next().unwrap_or_default().to_string();
-        if let Some(value) = parts.next() {
-            Ok(Self {
-                name,
+        let name = parts.next().unwrap_or_default();
+        match (parts.next(), name.strip_suffix(":json")) {
+            (Some(value), Some(name)) => {
+                let value: Value = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid JSON value for {name}: {e}"))?;
+                Ok(Self {
+                    name: name.to_string(),
+                    value: Metadata::from(value),
+                })
+            }
+            (Some(value), None) => Ok(Self {
+                name: name.to_string(),
                 value: Metadata::String(value.to_string()),
-            })
-        } else {
-            Ok(Self {
-                name,
+            }),
+            (None, _) => Ok(Self {
+                name: name.to_string(),
                 value: Metadata::Boolean(true),
-            })
+            }),
         }
     }
 }
+
+/// Build a metadata map from command-line properties.
+///
+/// Dotted names build nested maps. Properties given more than once are collected into a list.
 fn metadata_properties_to_value(meta_props: Vec<MetadataProperty>) -> Result<Value, Error> {
     use serde_json::map::Entry;
-    let mut map = serde_json::Map::new();
+    let mut root = serde_json::Map::new();
     for prop in meta_props {
-        let value = serde_json::to_value(prop.value)?;
-        match map.entry(prop.name) {
+        let value = Value::from(prop.value);
+        let mut path: Vec<&str> = prop.name.split('.').collect();
+        let leaf = path.pop().unwrap_or_default();
+        let mut map = &mut root;
+        for name in path {
+            let entry = map
+                .entry(name)
+                .or_insert_with(|| Value::Object(serde_json::Map::new()));
+            map = match entry {
+                Value::Object(map) => map,
+                _ => {
+                    let message = format!("Property {name} in {} is not a map", prop.name);
+                    return Err(DIDError::InvalidOptions(message).into());
+                }
+            };
+        }
+        match map.entry(leaf) {
             Entry::Vacant(entry) => {
                 entry.insert(value);
             }
@@ -87,16 +153,189 @@ fn metadata_properties_to_value(meta_props: Vec<MetadataProperty>) -> Result<Val
             }
         };
     }
-    Ok(Value::Object(map))
+    Ok(Value::Object(root))
 }
 
-fn read_jwk_file_opt(pathbuf_opt: &Option<PathBuf>) -> Result<Option<JWK>> {
-    let pathbuf = match pathbuf_opt {
-        Some(pb) => pb,
-        None => return Ok(None),
+/// Read a JSON or YAML options file, by file extension.
+fn read_options_file(path: &Path) -> Result<Value> {
+    let file = File::open(path).context("Open options file")?;
+    let reader = BufReader::new(file);
+    let value: Value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml") | Some("yml") => {
+            serde_yaml::from_reader(reader).context("Read YAML options file")?
+        }
+        _ => serde_json::from_reader(reader).context("Read JSON options file")?,
     };
-    let key_file = File::open(pathbuf).context("Opening JWK file")?;
+    if !value.is_object() {
+        let message = "Options file must contain a map".to_string();
+        return Err(DIDError::InvalidOptions(message).into());
+    }
+    Ok(value)
+}
+
+/// Merge `overrides` into `base`, recursively for maps.
+fn merge_values(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (name, value) in overrides {
+                match base.get_mut(&name) {
+                    Some(base_value) => merge_values(base_value, value),
+                    None => {
+                        base.insert(name, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// Build options from an optional options file and command-line properties, the latter taking
+/// precedence.
+fn options_to_value(
+    options_file: &Option<PathBuf>,
+    meta_props: Vec<MetadataProperty>,
+) -> Result<Value> {
+    let mut options = match options_file {
+        Some(path) => read_options_file(path)?,
+        None => Value::Object(serde_json::Map::new()),
+    };
+    merge_values(&mut options, metadata_properties_to_value(meta_props)?);
+    Ok(options)
+}
+
+fn read_jwk_file(path: &Path) -> Result<JWK> {
+    let key_file = File::open(path).context("Opening JWK file")?;
     let key_reader = BufReader::new(key_file);
     let jwk = serde_json::from_reader(key_reader).context("Reading JWK file")?;
-    Ok(Some(jwk))
+    Ok(jwk)
+}
+
+fn read_jwk_file_opt(pathbuf_opt: &Option<PathBuf>) -> Result<Option<JWK>> {
+    pathbuf_opt.as_deref().map(read_jwk_file).transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn error_report_codes() {
+        let err =
+            Error::from(DIDError::MethodNotSupported("example".to_string())).context("Resolve DID");
+        assert_eq!(
+            error_report(&err),
+            json!({
+                "error": "methodNotSupported",
+                "message": "Resolve DID: DID method not supported: example",
+            })
+        );
+
+        let err = Error::from(DIDMethodError::NotImplemented("update"));
+        assert_eq!(error_report(&err)["error"], "methodNotSupported");
+
+        let err = Error::from(DIDMethodError::Other(anyhow!(DIDError::Unauthorized(
+            "bad signature".to_string()
+        ))));
+        assert_eq!(error_report(&err)["error"], "unauthorized");
+
+        let err = Error::from(DIDError::Other {
+            code: "invalidDidDocument".to_string(),
+            message: "DID Document is not valid".to_string(),
+        });
+        assert_eq!(error_report(&err)["error"], "invalidDidDocument");
+
+        let err = anyhow!("Unable to read file");
+        assert_eq!(
+            error_report(&err),
+            json!({"error": "internalError", "message": "Unable to read file"})
+        );
+    }
+
+    fn props(args: &[&str]) -> Vec<MetadataProperty> {
+        args.iter().map(|arg| arg.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn metadata_property_forms() {
+        let value = metadata_properties_to_value(props(&[
+            "accept=application/did+json",
+            "maxAge:json=300",
+            "list:json=[1, \"a\"]",
+            "noCache",
+        ]))
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "accept": "application/did+json",
+                "maxAge": 300,
+                "list": [1, "a"],
+                "noCache": true,
+            })
+        );
+
+        // The value of a string property may contain `=` and `:json`.
+        let prop: MetadataProperty = "a:b=c:json=d".parse().unwrap();
+        assert_eq!(prop.name, "a:b");
+        assert_eq!(Value::from(prop.value), json!("c:json=d"));
+
+        assert!("maxAge:json=3OO".parse::<MetadataProperty>().is_err());
+    }
+
+    #[test]
+    fn metadata_property_paths() {
+        let value = metadata_properties_to_value(props(&[
+            "a.b=c",
+            "a.d:json={\"e\": 1}",
+            "a.d.f=g",
+            "h=i",
+            "h=j",
+        ]))
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "a": {"b": "c", "d": {"e": 1, "f": "g"}},
+                "h": ["i", "j"],
+            })
+        );
+    }
+
+    #[test]
+    fn metadata_property_path_conflicts() {
+        let err = metadata_properties_to_value(props(&["a=b", "a.c=d"])).unwrap_err();
+        assert_eq!(error_report(&err)["error"], "invalidOptions");
+
+        let err = metadata_properties_to_value(props(&["a.b.c=d", "a.b.c.e=f"])).unwrap_err();
+        assert_eq!(error_report(&err)["error"], "invalidOptions");
+    }
+
+    #[test]
+    fn options_files() {
+        let dir = std::env::temp_dir().join(format!("didkit-options-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json_path = dir.join("options.json");
+        std::fs::write(&json_path, r#"{"a": {"b": "c", "d": 1}, "e": true}"#).unwrap();
+        let yaml_path = dir.join("options.yaml");
+        std::fs::write(&yaml_path, "a:\n  b: c\n  d: 1\ne: true\n").unwrap();
+        let list_path = dir.join("list.yml");
+        std::fs::write(&list_path, "- a\n- b\n").unwrap();
+
+        let expected = json!({"a": {"b": "c", "d": 1}, "e": true});
+        assert_eq!(read_options_file(&json_path).unwrap(), expected);
+        assert_eq!(read_options_file(&yaml_path).unwrap(), expected);
+        let err = read_options_file(&list_path).unwrap_err();
+        assert_eq!(error_report(&err)["error"], "invalidOptions");
+
+        // Command-line properties take precedence, merging into maps.
+        let options = options_to_value(&Some(yaml_path), props(&["a.d:json=2", "f=g"])).unwrap();
+        assert_eq!(
+            options,
+            json!({"a": {"b": "c", "d": 2}, "e": true, "f": "g"})
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -5,7 +5,7 @@ use once_cell::sync::Lazy;
 
 use did_ion::DIDION;
 use did_web::DIDWEB;
-use ssi::ssi_dids::DIDMethods;
+use ssi::ssi_dids::{did_resolve::HTTPDIDResolver, DIDMethods};
 
 pub static DID_METHODS: Lazy<DIDMethods<'static>> = Lazy::new(|| {
     let mut methods = DIDMethods::default();
@@ -17,9 +17,30 @@ pub static DID_METHODS: Lazy<DIDMethods<'static>> = Lazy::new(|| {
             eprintln!("Unable to parse DID_ION_API_URL: {:?}", err);
             None
         }
+    })
+    .with_proof_of_work(match std::env::var("DID_ION_PROOF_OF_WORK") {
+        Ok(string) => string.parse().unwrap_or_else(|_| {
+            eprintln!("Unable to parse DID_ION_PROOF_OF_WORK: {:?}", string);
+            true
+        }),
+        Err(VarError::NotPresent) => true,
+        Err(VarError::NotUnicode(err)) => {
+            eprintln!("Unable to parse DID_ION_PROOF_OF_WORK: {:?}", err);
+            true
+        }
     });
 
     methods.insert(Box::new(DIDWEB));
     methods.insert(Box::new(DIDION));
+
+    match std::env::var("DID_RESOLVER") {
+        Ok(url) => {
+            methods.set_fallback(Box::new(HTTPDIDResolver::new(&url)));
+        }
+        Err(VarError::NotPresent) => {}
+        Err(VarError::NotUnicode(err)) => {
+            eprintln!("Unable to parse DID_RESOLVER: {:?}", err);
+        }
+    }
     methods
 });
@@ -3,5 +3,14 @@ mod did_methods;
 pub use crate::did_methods::DID_METHODS;
 
 pub use ssi;
-pub use ssi::ssi_dids::{DIDCreate, DIDMethods};
+pub use ssi::ssi_dids::bundle::{BundleEntry, StaticDIDResolver};
+pub use ssi::ssi_dids::cache::{CachingDIDResolver, FileCacheStore};
+pub use ssi::ssi_dids::did_resolve::{
+    DIDResolver, HTTPDIDResolver, ResolutionInputMetadata, ResolutionResult,
+};
+pub use ssi::ssi_dids::error::{Error, ErrorCode};
+pub use ssi::ssi_dids::{
+    DIDCreate, DIDDeactivate, DIDDocumentOperation, DIDMethodError, DIDMethodTransaction,
+    DIDMethods, DIDRecover, DIDUpdate, Document,
+};
 pub use ssi::ssi_jwk::JWK;
@@ -1,14 +1,27 @@
-use anyhow::{anyhow, Context, Result};
-use std::{convert::TryFrom, marker::PhantomData};
+use anyhow::{anyhow, ensure, Context, Result};
+use async_trait::async_trait;
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::HashMap, convert::TryFrom, marker::PhantomData};
 
 use ssi_dids::{
-    did_resolve::HTTPDIDResolver, DIDCreate, DIDMethod, DIDMethodError, DIDMethodTransaction,
+    did_resolve::{
+        DIDResolver, DocumentMetadata, HTTPDIDResolver, Metadata, ResolutionInputMetadata,
+        ResolutionMetadata, ResolutionResult,
+    },
+    error::{Error as DIDError, ErrorCode},
+    DIDCreate, DIDDeactivate, DIDMethod, DIDMethodError, DIDMethodTransaction, DIDRecover,
+    DIDUpdate, Document,
 };
 use ssi_jwk::JWK;
 
 use crate::{
     delta::patches::{DIDStatePatch, DocumentState, PublicKeyEntry, PublicKeyJwk},
-    operation::Operation,
+    operation::{CreateOperation, Operation},
+    proof_of_work::{
+        ProofOfWorkChallenge, ANSWER_HASH_HEADER, ANSWER_NONCE_HEADER, CHALLENGE_NONCE_HEADER,
+    },
     sidetree::Sidetree,
 };
 
@@ -28,10 +41,251 @@ impl<S: Sidetree> HTTPSidetreeDIDResolver<S> {
     }
 }
 
+/// Rename the `method` document metadata property of ION resolution results to
+/// [METHOD_METADATA_PROPERTY].
+fn normalize_method_metadata(doc_meta: &mut DocumentMetadata) {
+    if let Some(property_set) = doc_meta.property_set.as_mut() {
+        if let Some(method_metadata) = property_set.remove("method") {
+            property_set
+                .entry(METHOD_METADATA_PROPERTY.to_string())
+                .or_insert(method_metadata);
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Sidetree + Send + Sync> DIDResolver for HTTPSidetreeDIDResolver<S> {
+    /// Resolve a DID using the Sidetree node's [resolution
+    /// endpoint](https://identity.foundation/sidetree/api/#resolution).
+    ///
+    /// Deactivated DIDs resolve with `deactivated` document metadata. Long-form DIDs not known
+    /// to the node are resolved from their initial state, as by [LongFormSidetreeDIDResolver].
+    ///
+    /// Sidetree nodes do not support versioned resolution, so requests with `versionId` or
+    /// `versionTime` are not sent to the node: they are answered from the initial state of a
+    /// long-form DID, and fail with `methodNotSupported` for a short-form DID.
+    async fn resolve(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (
+        ResolutionMetadata,
+        Option<Document>,
+        Option<DocumentMetadata>,
+    ) {
+        let did_suffix = match S::did_suffix(did) {
+            Ok(did_suffix) => did_suffix,
+            Err(err) => return (ResolutionMetadata::from_error(err.code()), None, None),
+        };
+        let is_long_form = did != S::did_from_suffix(&did_suffix);
+        if input_metadata.is_versioned() {
+            if is_long_form {
+                return LongFormSidetreeDIDResolver::<S>::new()
+                    .resolve(did, input_metadata)
+                    .await;
+            }
+            return (
+                ResolutionMetadata::from_error(ErrorCode::MethodNotSupported),
+                None,
+                None,
+            );
+        }
+        let (status, body) = match self.http_did_resolver.request(did, input_metadata).await {
+            Ok(response) => response,
+            Err(err) => return (ResolutionMetadata::from_error(err.code()), None, None),
+        };
+        let result: ResolutionResult = match status {
+            // Nodes without long-form DID support do not find unpublished DIDs.
+            StatusCode::NOT_FOUND if is_long_form => {
+                return LongFormSidetreeDIDResolver::<S>::new()
+                    .resolve(did, input_metadata)
+                    .await
+            }
+            StatusCode::NOT_FOUND => {
+                return (
+                    ResolutionMetadata::from_error(ErrorCode::NotFound),
+                    None,
+                    None,
+                )
+            }
+            StatusCode::BAD_REQUEST => {
+                return (
+                    ResolutionMetadata::from_error(ErrorCode::InvalidDid),
+                    None,
+                    None,
+                )
+            }
+            // ION returns the deactivated DID document with status 410 Gone.
+            status if status.is_success() || status == StatusCode::GONE => {
+                match serde_json::from_slice(&body) {
+                    Ok(result) => result,
+                    Err(_) if status == StatusCode::GONE => ResolutionResult::default(),
+                    Err(_) => {
+                        return (
+                            ResolutionMetadata::from_error(ErrorCode::InternalError),
+                            None,
+                            None,
+                        )
+                    }
+                }
+            }
+            _ => {
+                return (
+                    ResolutionMetadata::from_error(ErrorCode::InternalError),
+                    None,
+                    None,
+                )
+            }
+        };
+        let (res_meta, doc_opt, doc_meta_opt) = result.into_parts();
+        if res_meta.error.is_some() {
+            return (res_meta, None, None);
+        }
+        let mut doc_meta = doc_meta_opt.unwrap_or_default();
+        normalize_method_metadata(&mut doc_meta);
+        if status == StatusCode::GONE {
+            doc_meta.deactivated = Some(true);
+        }
+        (res_meta, doc_opt, Some(doc_meta))
+    }
+}
+
+/// [DID Document Metadata](ssi_dids::did_resolve::DocumentMetadata) property for Sidetree method
+/// metadata
+pub const METHOD_METADATA_PROPERTY: &str = "methodMetadata";
+
+/// [Version id](DocumentMetadata::version_id) of the initial state of a Sidetree DID
+pub const INITIAL_VERSION_ID: &str = "0";
+
+/// DID Resolver for Sidetree [long-form DIDs][lfdu], without a Sidetree node
+///
+/// The DID document is computed from the initial state in the DID, so resolution works offline.
+/// The result does not reflect operations anchored after creation; short-form DIDs are not
+/// found.
+///
+/// The initial state is version [INITIAL_VERSION_ID], which can also be requested by
+/// `versionId`. Other versions are not found. `versionTime` is not supported, as the time the
+/// DID was anchored is not known without a Sidetree node.
+///
+/// [lfdu]: https://identity.foundation/sidetree/spec/v1.0.0/#long-form-did-uris
+pub struct LongFormSidetreeDIDResolver<S: Sidetree> {
+    pub _marker: PhantomData<S>,
+}
+
+impl<S: Sidetree> LongFormSidetreeDIDResolver<S> {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    /// Compute the DID document and document metadata of a long-form DID.
+    pub fn resolve_long_form(&self, did: &str) -> Result<(Document, DocumentMetadata), DIDError> {
+        let (did_suffix, create_operation) = S::parse_long_form_did(did)?;
+        let CreateOperation { delta, suffix_data } = create_operation;
+        let delta_string = S::json_canonicalization_scheme(&delta)
+            .map_err(|err| DIDError::Internal(err.to_string()))?;
+        if S::hash(delta_string.as_bytes()) != suffix_data.delta_hash {
+            return Err(DIDError::InvalidDid(format!(
+                "{did}: delta does not match delta hash"
+            )));
+        }
+        // A delta that is invalid or has a failed patch is void, leaving the initial document
+        // state empty.
+        let state = match S::validate_delta(&delta) {
+            Ok(()) => DocumentState::default()
+                .apply_patches(&delta.patches)
+                .unwrap_or_default(),
+            Err(_) => DocumentState::default(),
+        };
+        let doc = state
+            .to_document(did)
+            .map_err(|err| DIDError::InvalidDid(format!("{did}: {err:#}")))?;
+
+        let method_metadata = HashMap::from([
+            ("published".to_string(), Metadata::Boolean(false)),
+            (
+                "updateCommitment".to_string(),
+                Metadata::String(delta.update_commitment),
+            ),
+            (
+                "recoveryCommitment".to_string(),
+                Metadata::String(suffix_data.recovery_commitment),
+            ),
+        ]);
+        let property_set = HashMap::from([
+            (
+                METHOD_METADATA_PROPERTY.to_string(),
+                Metadata::Map(method_metadata),
+            ),
+            (
+                "equivalentId".to_string(),
+                Metadata::List(vec![Metadata::String(S::did_from_suffix(&did_suffix))]),
+            ),
+        ]);
+        let doc_meta = DocumentMetadata {
+            version_id: Some(INITIAL_VERSION_ID.to_string()),
+            property_set: Some(property_set),
+            ..Default::default()
+        };
+        Ok((doc, doc_meta))
+    }
+}
+
+impl<S: Sidetree> Default for LongFormSidetreeDIDResolver<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<S: Sidetree + Send + Sync> DIDResolver for LongFormSidetreeDIDResolver<S> {
+    async fn resolve(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (
+        ResolutionMetadata,
+        Option<Document>,
+        Option<DocumentMetadata>,
+    ) {
+        let did_suffix = match S::did_suffix(did) {
+            Ok(did_suffix) => did_suffix,
+            Err(err) => return (ResolutionMetadata::from_error(err.code()), None, None),
+        };
+        if input_metadata.version_time.is_some() {
+            return (
+                ResolutionMetadata::from_error(ErrorCode::MethodNotSupported),
+                None,
+                None,
+            );
+        }
+        // Only the initial version of the DID document is known from a long-form DID.
+        let is_initial_version = match &input_metadata.version_id {
+            Some(version_id) => version_id == INITIAL_VERSION_ID,
+            None => true,
+        };
+        if did == S::did_from_suffix(&did_suffix) || !is_initial_version {
+            return (
+                ResolutionMetadata::from_error(ErrorCode::NotFound),
+                None,
+                None,
+            );
+        }
+        match self.resolve_long_form(did) {
+            Ok((doc, doc_meta)) => (ResolutionMetadata::default(), Some(doc), Some(doc_meta)),
+            Err(err) => (ResolutionMetadata::from_error(err.code()), None, None),
+        }
+    }
+}
+
 /// Sidetree DID Method client implementation
 pub struct SidetreeClient<S: Sidetree> {
     pub resolver: Option<HTTPSidetreeDIDResolver<S>>,
     pub endpoint: Option<String>,
+    /// Solve the node's [proof-of-work challenge](ProofOfWorkChallenge) before submitting an
+    /// operation, as public ION nodes require
+    pub proof_of_work: bool,
 }
 
 impl<S: Sidetree> SidetreeClient<S> {
@@ -42,10 +296,19 @@ impl<S: Sidetree> SidetreeClient<S> {
         Self {
             endpoint: api_url_opt,
             resolver: resolver_opt,
+            proof_of_work: true,
         }
     }
+
+    /// Enable or disable the proof-of-work challenge, e.g. to submit to a private node that
+    /// does not require it.
+    pub fn with_proof_of_work(mut self, proof_of_work: bool) -> Self {
+        self.proof_of_work = proof_of_work;
+        self
+    }
 }
 
+#[async_trait]
 impl<S: Sidetree + Send + Sync> DIDMethod for SidetreeClient<S> {
     fn name(&self) -> &'static str {
         "ion"
@@ -73,16 +336,208 @@ impl<S: Sidetree + Send + Sync> DIDMethod for SidetreeClient<S> {
 
         Ok(tx)
     }
+
+    fn update(&self, update: DIDUpdate) -> Result<DIDMethodTransaction, DIDMethodError> {
+        let DIDUpdate {
+            did,
+            update_key,
+            new_update_key,
+            operation,
+            options,
+        } = update;
+        if let Some(opt) = options.keys().next() {
+            return Err(DIDMethodError::OptionNotSupported {
+                operation: "update",
+                option: opt.clone(),
+            });
+        }
+        let did_suffix = S::did_suffix(&did)?;
+        let update_key = update_key.ok_or_else(|| anyhow!("Missing required update key"))?;
+        let new_update_key =
+            new_update_key.ok_or_else(|| anyhow!("Missing required new update key"))?;
+        let new_update_pk =
+            PublicKeyJwk::try_from(new_update_key.to_public()).context("Convert new update key")?;
+        let patch = DIDStatePatch::try_from(operation).context("Convert DID document operation")?;
+        let operation = S::update(&did_suffix, &update_key, &new_update_pk, vec![patch])
+            .context("Construct Update operation")?;
+        let tx = Self::operation_to_transaction(Operation::Update(operation))
+            .context("Construct update transaction")?;
+        Ok(tx)
+    }
+
+    fn recover(&self, recover: DIDRecover) -> Result<DIDMethodTransaction, DIDMethodError> {
+        let DIDRecover {
+            did,
+            recovery_key,
+            new_update_key,
+            new_recovery_key,
+            new_verification_key,
+            mut options,
+        } = recover;
+        let anchor_origin = match options.remove("anchorOrigin") {
+            Some(Value::String(anchor_origin)) => Some(anchor_origin),
+            Some(_) => return Err(anyhow!("anchorOrigin option must be a string").into()),
+            None => None,
+        };
+        if let Some(opt) = options.keys().next() {
+            return Err(DIDMethodError::OptionNotSupported {
+                operation: "recover",
+                option: opt.clone(),
+            });
+        }
+        let did_suffix = S::did_suffix(&did)?;
+        let recovery_key = recovery_key.ok_or_else(|| anyhow!("Missing required recovery key"))?;
+        let (new_update_pk, new_recovery_pk, patches) =
+            new_did_state(new_update_key, new_recovery_key, new_verification_key)
+                .context("Prepare keys for DID recovery")?;
+        let operation = S::recover_existing(
+            &did_suffix,
+            &recovery_key,
+            &new_update_pk,
+            &new_recovery_pk,
+            patches,
+            anchor_origin,
+        )
+        .context("Construct Recover operation")?;
+        let tx = Self::operation_to_transaction(Operation::Recover(operation))
+            .context("Construct recover transaction")?;
+        Ok(tx)
+    }
+
+    fn deactivate(
+        &self,
+        deactivate: DIDDeactivate,
+    ) -> Result<DIDMethodTransaction, DIDMethodError> {
+        let DIDDeactivate { did, key, options } = deactivate;
+        if let Some(opt) = options.keys().next() {
+            return Err(DIDMethodError::OptionNotSupported {
+                operation: "deactivate",
+                option: opt.clone(),
+            });
+        }
+        let did_suffix = S::did_suffix(&did)?;
+        let recovery_key = key.ok_or_else(|| anyhow!("Missing required recovery key"))?;
+        let operation =
+            S::deactivate(&did_suffix, &recovery_key).context("Construct Deactivate operation")?;
+        let tx = Self::operation_to_transaction(Operation::Deactivate(operation))
+            .context("Construct deactivate transaction")?;
+        Ok(tx)
+    }
+
+    fn did_from_transaction(&self, tx: &DIDMethodTransaction) -> Result<String, DIDMethodError> {
+        let op = Self::transaction_to_operation(tx).context("Read Sidetree operation")?;
+        let did = did_from_operation::<S>(&op).context("Get DID of Sidetree operation")?;
+        Ok(did)
+    }
+
+    /// Submit a Sidetree operation to the node's [operations
+    /// endpoint](https://identity.foundation/sidetree/api/#sidetree-operations).
+    ///
+    /// If [proof of work](SidetreeClient::proof_of_work) is enabled, the node's challenge is
+    /// fetched from its `proof-of-work-challenge` endpoint and solved first.
+    async fn submit_transaction(&self, tx: DIDMethodTransaction) -> Result<Value, DIDMethodError> {
+        let op = Self::transaction_to_operation(&tx).context("Read Sidetree operation")?;
+        S::validate_operation(&op).context("Validate Sidetree operation")?;
+        let endpoint = self
+            .endpoint
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing Sidetree REST API endpoint"))?;
+        let url = format!("{endpoint}operations");
+        let client = reqwest::Client::builder()
+            .build()
+            .context("Build HTTP client")?;
+        // The answer to the challenge is bound to the exact request body.
+        let body = serde_json::to_string(&op).context("Serialize Sidetree operation")?;
+        let mut request = client
+            .post(&url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if self.proof_of_work {
+            let challenge_url = format!("{endpoint}proof-of-work-challenge");
+            let challenge = ProofOfWorkChallenge::fetch(&client, &challenge_url)
+                .await
+                .context("Get proof-of-work challenge")?;
+            let answer = challenge
+                .solve(&body)
+                .await
+                .context("Solve proof-of-work challenge")?;
+            request = request
+                .header(CHALLENGE_NONCE_HEADER, &challenge.challenge_nonce)
+                .header(ANSWER_NONCE_HEADER, &answer.answer_nonce)
+                .header(ANSWER_HASH_HEADER, &answer.answer_hash);
+        }
+        let resp = request.send().await.context("Send HTTP request")?;
+        let status = resp.status();
+        let body = resp.text().await.context("Read HTTP response")?;
+        if !status.is_success() {
+            return Err(anyhow!("Sidetree node returned error ({status}): {body}").into());
+        }
+        if body.is_empty() {
+            return Ok(Value::Null);
+        }
+        let value = serde_json::from_str(&body).context("Parse Sidetree node response")?;
+        Ok(value)
+    }
+
+    /// Resolve DIDs using the Sidetree node, if its REST API URL is set.
+    fn to_resolver(&self) -> Option<&dyn DIDResolver> {
+        self.resolver
+            .as_ref()
+            .map(|resolver| resolver as &dyn DIDResolver)
+    }
 }
 
 impl<S: Sidetree> SidetreeClient<S> {
     fn operation_to_transaction(op: Operation) -> Result<DIDMethodTransaction> {
-        let value = serde_json::to_value(op).context("Convert operation to value")?;
+        let did = did_from_operation::<S>(&op)?;
+        let long_form_did = match &op {
+            Operation::Create(create_operation) => {
+                Some(S::long_form_did(create_operation).context("Construct long-form DID")?)
+            }
+            _ => None,
+        };
+        let op_value = serde_json::to_value(op).context("Convert operation to value")?;
+        let mut value = serde_json::json!({ "sidetreeOperation": op_value });
+        if let Some(long_form_did) = long_form_did {
+            value["longFormDid"] = Value::String(long_form_did);
+        }
         Ok(DIDMethodTransaction {
             did_method: S::METHOD.to_string(),
-            value: serde_json::json!({ "sidetreeOperation": value }),
+            did: Some(did),
+            value,
         })
     }
+
+    fn transaction_to_operation(tx: &DIDMethodTransaction) -> Result<Operation> {
+        ensure!(
+            tx.did_method == S::METHOD,
+            "Expected DID method {}, but transaction is for {}",
+            S::METHOD,
+            tx.did_method
+        );
+        let op_value = tx
+            .value
+            .get("sidetreeOperation")
+            .ok_or_else(|| anyhow!("Missing sidetreeOperation property"))?;
+        let op = Operation::deserialize(op_value).context("Convert value to operation")?;
+        Ok(op)
+    }
+}
+
+/// Get the DID that a Sidetree operation is for, e.g. the DID created by a Create operation.
+pub fn did_from_operation<S: Sidetree>(op: &Operation) -> Result<String> {
+    let did_suffix = op.did_suffix::<S>().context("Get DID Suffix")?;
+    Ok(S::did_from_suffix(&did_suffix))
+}
+
+/// Get the DID that a Sidetree DID method transaction is for, e.g. the DID created by a Create
+/// transaction.
+///
+/// The DID is computed from the Sidetree operation in the transaction.
+pub fn did_from_transaction<S: Sidetree>(tx: &DIDMethodTransaction) -> Result<String> {
+    let op =
+        SidetreeClient::<S>::transaction_to_operation(tx).context("Read Sidetree operation")?;
+    did_from_operation::<S>(&op)
 }
 
 fn new_did_state(
@@ -92,9 +547,9 @@ fn new_did_state(
 ) -> Result<(PublicKeyJwk, PublicKeyJwk, Vec<DIDStatePatch>)> {
     let update_key = update_key.ok_or_else(|| anyhow!("Missing required update key"))?;
     let recovery_key = recovery_key.ok_or_else(|| anyhow!("Missing required recovery key"))?;
-    // TODO: validate jwk
-    let update_pk = PublicKeyJwk::try_from(update_key).context("Convert update key")?;
-    let recovery_pk = PublicKeyJwk::try_from(recovery_key).context("Convert recovery key")?;
+    let update_pk = PublicKeyJwk::try_from(update_key.to_public()).context("Convert update key")?;
+    let recovery_pk =
+        PublicKeyJwk::try_from(recovery_key.to_public()).context("Convert recovery key")?;
     let mut patches = vec![];
     if let Some(verification_key) = verification_key {
         let public_key_entry = PublicKeyEntry::try_from(verification_key)
@@ -108,3 +563,644 @@ fn new_did_state(
     }
     Ok((update_pk, recovery_pk, patches))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{delta::patches::ServiceEndpointEntry, proof_of_work::answer_hash, DIDION, ION};
+    use json_patch::Patch;
+    use serde_json::json;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    /// HTTP request received by a [MockNode]
+    #[derive(Debug, Clone)]
+    struct Request {
+        method: String,
+        path: String,
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    type Respond = dyn Fn(&Request) -> (u16, String) + Send + Sync;
+
+    /// Local HTTP server standing in for a Sidetree node
+    ///
+    /// Responses are chosen by a function of the request. Requests are recorded for
+    /// assertions.
+    struct MockNode {
+        url: String,
+        requests: Arc<Mutex<Vec<Request>>>,
+    }
+
+    impl MockNode {
+        async fn start(
+            respond: impl Fn(&Request) -> (u16, String) + Send + Sync + 'static,
+        ) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let respond: Arc<Respond> = Arc::new(respond);
+            let recorded = requests.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let respond = respond.clone();
+                    let recorded = recorded.clone();
+                    tokio::spawn(async move {
+                        if let Some(request) = read_request(stream, &*respond).await {
+                            recorded.lock().unwrap().push(request);
+                        }
+                    });
+                }
+            });
+            Self { url, requests }
+        }
+
+        fn requests(&self) -> Vec<Request> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    async fn read_request(mut stream: TcpStream, respond: &Respond) -> Option<Request> {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        let head_end = loop {
+            let n = stream.read(&mut buf).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            data.extend_from_slice(&buf[..n]);
+            if let Some(i) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i;
+            }
+        };
+        let head = String::from_utf8(data[..head_end].to_vec()).ok()?;
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next()?.split(' ');
+        let method = request_line.next()?.to_string();
+        let path = request_line.next()?.to_string();
+        let headers: HashMap<String, String> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        let content_length: usize = headers
+            .get("content-length")
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(0);
+        let mut body = data[head_end + 4..].to_vec();
+        while body.len() < content_length {
+            let n = stream.read(&mut buf).await.ok()?;
+            if n == 0 {
+                break;
+            }
+            body.extend_from_slice(&buf[..n]);
+        }
+        let request = Request {
+            method,
+            path,
+            headers,
+            body: String::from_utf8(body).ok()?,
+        };
+        let (status, body) = respond(&request);
+        let response = format!(
+            "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await.ok()?;
+        stream.shutdown().await.ok()?;
+        Some(request)
+    }
+
+    fn create_transaction() -> DIDMethodTransaction {
+        let client = DIDION::new(None);
+        client
+            .create(DIDCreate {
+                update_key: Some(JWK::generate_secp256k1().unwrap()),
+                recovery_key: Some(JWK::generate_secp256k1().unwrap()),
+                verification_key: Some(JWK::generate_secp256k1().unwrap()),
+                options: Default::default(),
+            })
+            .unwrap()
+    }
+
+    fn client(node: &MockNode) -> DIDION {
+        DIDION::new(Some(node.url.clone())).with_proof_of_work(false)
+    }
+
+    #[tokio::test]
+    async fn submit_posts_operation() {
+        let node = MockNode::start(|_| (200, r#"{"ok":true}"#.to_string())).await;
+        let tx = create_transaction();
+        let operation = tx.value["sidetreeOperation"].clone();
+        let response = client(&node).submit_transaction(tx).await.unwrap();
+        assert_eq!(response, json!({"ok": true}));
+
+        let requests = node.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/operations");
+        assert_eq!(request.headers["content-type"], "application/json");
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body, operation);
+    }
+
+    #[tokio::test]
+    async fn submit_surfaces_node_error() {
+        let node = MockNode::start(|_| (400, r#"{"code":"bad_request"}"#.to_string())).await;
+        let err = client(&node)
+            .submit_transaction(create_transaction())
+            .await
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("400"), "{}", message);
+        assert!(message.contains("bad_request"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn submit_empty_response_is_null() {
+        let node = MockNode::start(|_| (200, String::new())).await;
+        let response = client(&node)
+            .submit_transaction(create_transaction())
+            .await
+            .unwrap();
+        assert_eq!(response, Value::Null);
+    }
+
+    #[tokio::test]
+    async fn submit_rejects_other_method() {
+        let node = MockNode::start(|_| (200, String::new())).await;
+        let mut tx = create_transaction();
+        tx.did_method = "web".to_string();
+        let err = client(&node).submit_transaction(tx).await.unwrap_err();
+        assert!(
+            err.to_string().contains("Read Sidetree operation"),
+            "{}",
+            err
+        );
+        assert!(node.requests().is_empty());
+        assert!(
+            SidetreeClient::<ION>::transaction_to_operation(&DIDMethodTransaction {
+                did_method: "ion".to_string(),
+                did: None,
+                value: json!({}),
+            })
+            .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn submit_solves_proof_of_work() {
+        let challenge_nonce = "d1f6c8b0a7a35c7de1f56f3c0c1d2e3f";
+        let largest_allowed_hash = format!("7{}", "f".repeat(63));
+        let challenge = json!({
+            "challengeNonce": challenge_nonce,
+            "largestAllowedHash": largest_allowed_hash,
+            "validDurationInMinutes": 10,
+        });
+        let node = MockNode::start(move |request| match request.path.as_str() {
+            "/proof-of-work-challenge" => (200, challenge.to_string()),
+            _ => (200, String::new()),
+        })
+        .await;
+        let client = DIDION::new(Some(node.url.clone()));
+        assert!(client.proof_of_work);
+        client
+            .submit_transaction(create_transaction())
+            .await
+            .unwrap();
+
+        let requests = node.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/proof-of-work-challenge");
+        let request = &requests[1];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/operations");
+        assert_eq!(request.headers["challenge-nonce"], challenge_nonce);
+        let answer_nonce = &request.headers["answer-nonce"];
+        let hash = &request.headers["answer-hash"];
+        assert_eq!(
+            &answer_hash(challenge_nonce, answer_nonce, &request.body).unwrap(),
+            hash
+        );
+        assert!(*hash <= largest_allowed_hash);
+    }
+
+    #[tokio::test]
+    async fn submit_surfaces_challenge_error() {
+        let node = MockNode::start(|_| (500, "unavailable".to_string())).await;
+        let err = DIDION::new(Some(node.url.clone()))
+            .submit_transaction(create_transaction())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("proof-of-work"), "{}", err);
+        assert_eq!(node.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn submit_requires_endpoint() {
+        let err = DIDION::new(None)
+            .submit_transaction(create_transaction())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("endpoint"), "{}", err);
+    }
+
+    const DID: &str = "did:ion:EiClkZMDxPKqC9c-umQfTkR8vvZ9JPhl_xLDI9Nfk38w5w";
+
+    /// Resolution result of a published DID, as returned by an ION node
+    fn published_response(method_metadata_property: &str) -> Value {
+        json!({
+            "@context": "https://w3id.org/did-resolution/v1",
+            "didDocument": {
+                "id": DID,
+                "@context": [
+                    "https://www.w3.org/ns/did/v1",
+                    {"@base": DID}
+                ],
+                "service": [{
+                    "id": "#linkeddomains",
+                    "type": "LinkedDomains",
+                    "serviceEndpoint": {"origins": ["https://www.linkeddomains.com/"]}
+                }],
+                "verificationMethod": [{
+                    "id": "#someKeyId",
+                    "controller": DID,
+                    "type": "EcdsaSecp256k1VerificationKey2019",
+                    "publicKeyJwk": {
+                        "kty": "EC",
+                        "crv": "secp256k1",
+                        "x": "WfY7Px6AgH6x-_dgAoRbg8weYRJA36ON-gQiFnETrqw",
+                        "y": "IzFx3BUGztK0cyDStiunXbrZYYTtKbOUzx16SUK0sAY"
+                    }
+                }],
+                "authentication": ["#someKeyId"]
+            },
+            "didDocumentMetadata": {
+                method_metadata_property: {
+                    "published": true,
+                    "recoveryCommitment": "EiBfOZdMtU6OBw8Pk879QtZ-2J-9FbbjSZyoaA_bqD4zhA",
+                    "updateCommitment": "EiDOrcmPtfMHuwIWN6YoihdeIPxOKDHy3D6sdMXu_7CN0w"
+                },
+                "canonicalId": DID
+            }
+        })
+    }
+
+    /// Resolution result of a deactivated DID, as returned by an ION node with status 410
+    fn deactivated_response() -> Value {
+        json!({
+            "@context": "https://w3id.org/did-resolution/v1",
+            "didDocument": {
+                "id": DID,
+                "@context": [
+                    "https://www.w3.org/ns/did/v1",
+                    {"@base": DID}
+                ]
+            },
+            "didDocumentMetadata": {
+                "method": {"published": true},
+                "canonicalId": DID
+            }
+        })
+    }
+
+    fn resolver(node: &MockNode) -> HTTPSidetreeDIDResolver<ION> {
+        HTTPSidetreeDIDResolver::new(&node.url)
+    }
+
+    #[tokio::test]
+    async fn resolve_published() {
+        let node = MockNode::start(|_| {
+            (
+                200,
+                published_response(METHOD_METADATA_PROPERTY).to_string(),
+            )
+        })
+        .await;
+        let (res_meta, doc, doc_meta) = resolver(&node)
+            .resolve(DID, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, None);
+        assert_eq!(doc.unwrap().id, DID);
+        let doc_meta = doc_meta.unwrap();
+        assert_eq!(doc_meta.deactivated, None);
+        assert!(matches!(
+            method_metadata(&doc_meta).get("published"),
+            Some(Metadata::Boolean(true))
+        ));
+
+        let requests = node.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, format!("/identifiers/{}", DID));
+    }
+
+    #[tokio::test]
+    async fn resolve_normalizes_method_metadata() {
+        // ION nodes return method metadata as `method`.
+        let node = MockNode::start(|_| (200, published_response("method").to_string())).await;
+        let (res_meta, _, doc_meta) = resolver(&node)
+            .resolve(DID, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, None);
+        let doc_meta = doc_meta.unwrap();
+        assert!(matches!(
+            method_metadata(&doc_meta).get("published"),
+            Some(Metadata::Boolean(true))
+        ));
+        assert!(!doc_meta.property_set.unwrap().contains_key("method"));
+    }
+
+    #[tokio::test]
+    async fn resolve_deactivated() {
+        let node = MockNode::start(|_| (410, deactivated_response().to_string())).await;
+        let (res_meta, doc, doc_meta) = resolver(&node)
+            .resolve(DID, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, None);
+        let doc = doc.unwrap();
+        assert_eq!(doc.id, DID);
+        assert!(doc.verification_method.is_none());
+        assert_eq!(doc_meta.unwrap().deactivated, Some(true));
+    }
+
+    #[tokio::test]
+    async fn resolve_not_found() {
+        let node = MockNode::start(|_| (404, r#"{"code":"not_found"}"#.to_string())).await;
+        let (res_meta, doc, _) = resolver(&node)
+            .resolve(DID, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, Some(ErrorCode::NotFound));
+        assert!(doc.is_none());
+    }
+
+    #[tokio::test]
+    async fn resolve_invalid_did() {
+        let node = MockNode::start(|_| (400, r#"{"code":"did_incorrect"}"#.to_string())).await;
+        let (res_meta, _, _) = resolver(&node)
+            .resolve(DID, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, Some(ErrorCode::InvalidDid));
+
+        // Malformed DIDs are rejected without a request.
+        let (res_meta, _, _) = resolver(&node)
+            .resolve("did:ion:not+a/suffix", &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, Some(ErrorCode::InvalidDid));
+        assert_eq!(node.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn resolve_unpublished_long_form() {
+        let node = MockNode::start(|_| (404, r#"{"code":"not_found"}"#.to_string())).await;
+        let tx = create_transaction();
+        let did = tx.did.unwrap();
+        let long_form_did = tx.value["longFormDid"].as_str().unwrap();
+        let (res_meta, doc, doc_meta) = resolver(&node)
+            .resolve(long_form_did, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, None);
+        let doc = doc.unwrap();
+        assert_eq!(doc.id, long_form_did);
+        assert_eq!(doc.verification_method.unwrap().len(), 1);
+        let doc_meta = doc_meta.unwrap();
+        assert!(matches!(
+            method_metadata(&doc_meta).get("published"),
+            Some(Metadata::Boolean(false))
+        ));
+        assert_eq!(node.requests().len(), 1);
+
+        let (res_meta, _, _) = resolver(&node)
+            .resolve(&did, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, Some(ErrorCode::NotFound));
+    }
+
+    #[tokio::test]
+    async fn resolve_version_of_long_form() {
+        let node = MockNode::start(|_| (200, published_response("method").to_string())).await;
+        let tx = create_transaction();
+        let long_form_did = tx.value["longFormDid"].as_str().unwrap();
+        let version_id = |version_id: &str| ResolutionInputMetadata {
+            version_id: Some(version_id.to_string()),
+            ..Default::default()
+        };
+
+        let (res_meta, doc, doc_meta) = resolver(&node)
+            .resolve(long_form_did, &version_id(INITIAL_VERSION_ID))
+            .await;
+        assert_eq!(res_meta.error, None);
+        assert_eq!(doc.unwrap().id, long_form_did);
+        let doc_meta = doc_meta.unwrap();
+        assert_eq!(doc_meta.version_id.as_deref(), Some(INITIAL_VERSION_ID));
+        assert!(matches!(
+            method_metadata(&doc_meta).get("published"),
+            Some(Metadata::Boolean(false))
+        ));
+
+        let (res_meta, doc, _) = resolver(&node)
+            .resolve(long_form_did, &version_id("1"))
+            .await;
+        assert_eq!(res_meta.error, Some(ErrorCode::NotFound));
+        assert!(doc.is_none());
+
+        let version_time = ResolutionInputMetadata {
+            version_time: Some("2022-01-01T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let (res_meta, _, _) = resolver(&node).resolve(long_form_did, &version_time).await;
+        assert_eq!(res_meta.error, Some(ErrorCode::MethodNotSupported));
+
+        // The node ignores version parameters, so it is not asked.
+        assert!(node.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn resolve_version_of_short_form() {
+        let node = MockNode::start(|_| (200, published_response("method").to_string())).await;
+        let input_metadata = ResolutionInputMetadata {
+            version_id: Some("1".to_string()),
+            ..Default::default()
+        };
+        let (res_meta, doc, _) = resolver(&node).resolve(DID, &input_metadata).await;
+        assert_eq!(res_meta.error, Some(ErrorCode::MethodNotSupported));
+        assert!(doc.is_none());
+        assert!(node.requests().is_empty());
+    }
+
+    fn method_metadata(doc_meta: &DocumentMetadata) -> &HashMap<String, Metadata> {
+        match doc_meta
+            .property_set
+            .as_ref()
+            .and_then(|property_set| property_set.get(METHOD_METADATA_PROPERTY))
+        {
+            Some(Metadata::Map(method_metadata)) => method_metadata,
+            other => panic!("Expected method metadata map: {:?}", other),
+        }
+    }
+
+    /// Create operation of a DID with an initial state from `patches`
+    fn create_operation(patches: Vec<DIDStatePatch>) -> CreateOperation {
+        let update_pk =
+            PublicKeyJwk::try_from(JWK::generate_secp256k1().unwrap().to_public()).unwrap();
+        let recovery_pk =
+            PublicKeyJwk::try_from(JWK::generate_secp256k1().unwrap().to_public()).unwrap();
+        match ION::create_existing(&update_pk, &recovery_pk, patches).unwrap() {
+            Operation::Create(create_operation) => create_operation,
+            other => panic!("Expected create operation: {:?}", other),
+        }
+    }
+
+    fn public_key_entry() -> PublicKeyEntry {
+        PublicKeyEntry::try_from(JWK::generate_secp256k1().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn resolve_long_form_offline() {
+        let tx = create_transaction();
+        let did = tx.did.unwrap();
+        let long_form_did = tx.value["longFormDid"].as_str().unwrap();
+        let resolver = LongFormSidetreeDIDResolver::<ION>::new();
+        let (res_meta, doc, doc_meta) = resolver
+            .resolve(long_form_did, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, None);
+        let doc = doc.unwrap();
+        assert_eq!(doc.id, long_form_did);
+        assert_eq!(doc.verification_method.unwrap().len(), 1);
+        let doc_meta = doc_meta.unwrap();
+        assert_eq!(doc_meta.version_id.as_deref(), Some(INITIAL_VERSION_ID));
+        let method_metadata = method_metadata(&doc_meta);
+        assert!(matches!(
+            method_metadata.get("published"),
+            Some(Metadata::Boolean(false))
+        ));
+        assert!(method_metadata.contains_key("updateCommitment"));
+        assert!(method_metadata.contains_key("recoveryCommitment"));
+
+        // Short-form DIDs need a Sidetree node.
+        let (res_meta, _, _) = resolver
+            .resolve(&did, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, Some(ErrorCode::NotFound));
+    }
+
+    #[tokio::test]
+    async fn resolve_long_form_versions() {
+        let tx = create_transaction();
+        let long_form_did = tx.value["longFormDid"].as_str().unwrap();
+        let resolver = LongFormSidetreeDIDResolver::<ION>::new();
+        let version_id = |version_id: &str| ResolutionInputMetadata {
+            version_id: Some(version_id.to_string()),
+            ..Default::default()
+        };
+
+        let (res_meta, doc, doc_meta) = resolver
+            .resolve(long_form_did, &version_id(INITIAL_VERSION_ID))
+            .await;
+        assert_eq!(res_meta.error, None);
+        assert_eq!(doc.unwrap().id, long_form_did);
+        let doc_meta = doc_meta.unwrap();
+        assert_eq!(doc_meta.version_id.as_deref(), Some(INITIAL_VERSION_ID));
+        assert!(matches!(
+            method_metadata(&doc_meta).get("published"),
+            Some(Metadata::Boolean(false))
+        ));
+
+        let (res_meta, doc, _) = resolver.resolve(long_form_did, &version_id("1")).await;
+        assert_eq!(res_meta.error, Some(ErrorCode::NotFound));
+        assert!(doc.is_none());
+
+        let version_time = ResolutionInputMetadata {
+            version_time: Some("2022-01-01T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let (res_meta, _, _) = resolver.resolve(long_form_did, &version_time).await;
+        assert_eq!(res_meta.error, Some(ErrorCode::MethodNotSupported));
+    }
+
+    #[tokio::test]
+    async fn resolve_long_form_delta_hash_mismatch() {
+        let mut create_operation = create_operation(vec![DIDStatePatch::AddPublicKeys {
+            public_keys: vec![public_key_entry()],
+        }]);
+        create_operation.delta.update_commitment =
+            create_operation.suffix_data.recovery_commitment.clone();
+        let long_form_did = ION::long_form_did(&create_operation).unwrap();
+        let (res_meta, doc, _) = LongFormSidetreeDIDResolver::<ION>::new()
+            .resolve(&long_form_did, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, Some(ErrorCode::InvalidDid));
+        assert!(doc.is_none());
+    }
+
+    #[tokio::test]
+    async fn resolve_long_form_failed_patch_voids_delta() {
+        let failing_patch: Patch =
+            serde_json::from_value(json!([{"op": "remove", "path": "/missing"}])).unwrap();
+        let create_operation = create_operation(vec![
+            DIDStatePatch::AddPublicKeys {
+                public_keys: vec![public_key_entry()],
+            },
+            DIDStatePatch::IetfJsonPatch {
+                patches: failing_patch,
+            },
+        ]);
+        let long_form_did = ION::long_form_did(&create_operation).unwrap();
+        let (res_meta, doc, doc_meta) = LongFormSidetreeDIDResolver::<ION>::new()
+            .resolve(&long_form_did, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, None);
+        let doc = doc.unwrap();
+        assert_eq!(doc.id, long_form_did);
+        assert!(doc.verification_method.is_none());
+        assert!(doc.authentication.is_none());
+        // The commitments still apply, so the DID can be updated or recovered.
+        assert!(method_metadata(&doc_meta.unwrap()).contains_key("updateCommitment"));
+    }
+
+    #[tokio::test]
+    async fn resolve_long_form_invalid_delta_is_void() {
+        let mut create_operation = create_operation(Vec::new());
+        // Bypass validation in create_existing with a service type that is too long.
+        let service: ServiceEndpointEntry = serde_json::from_value(json!({
+            "id": "domain",
+            "type": "T".repeat(ION::MAX_SERVICE_TYPE_LENGTH + 1),
+            "serviceEndpoint": "https://example.com",
+        }))
+        .unwrap();
+        create_operation.delta.patches = vec![DIDStatePatch::AddServices {
+            services: vec![service],
+        }];
+        let delta_string = ION::json_canonicalization_scheme(&create_operation.delta).unwrap();
+        create_operation.suffix_data.delta_hash = ION::hash(delta_string.as_bytes());
+        let long_form_did = ION::long_form_did(&create_operation).unwrap();
+        let (res_meta, doc, _) = LongFormSidetreeDIDResolver::<ION>::new()
+            .resolve(&long_form_did, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, None);
+        assert!(doc.unwrap().service.is_none());
+    }
+
+    #[tokio::test]
+    async fn resolve_long_form_empty_state() {
+        let create_operation = create_operation(Vec::new());
+        let long_form_did = ION::long_form_did(&create_operation).unwrap();
+        let (res_meta, doc, _) = LongFormSidetreeDIDResolver::<ION>::new()
+            .resolve(&long_form_did, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, None);
+        let doc = doc.unwrap();
+        assert_eq!(doc.id, long_form_did);
+        assert!(doc.verification_method.is_none());
+        assert!(doc.service.is_none());
+    }
+}
@@ -1,19 +1,26 @@
-use std::convert::TryFrom;
+use std::{
+    collections::{BTreeMap, HashSet},
+    convert::TryFrom,
+};
 
-use anyhow::{Context, Error};
+use anyhow::{anyhow, Context, Error};
 use json_patch::Patch;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use ssi_dids::{ServiceEndpoint, VerificationRelationship};
+use ssi_dids::{
+    service::TypedService, Context as JsonLdContext, DIDDocumentOperation, Document, OneOrMany,
+    Service, ServiceEndpoint, VerificationMethod, VerificationMethodMap, VerificationRelationship,
+    DEFAULT_CONTEXT,
+};
 use ssi_jwk::JWK;
 
-use crate::error::PublicKeyJwkFromJWKError;
+use crate::error::{DIDStatePatchError, PublicKeyJwkFromJWKError};
 
 /// [DID State Patch][dsp] using a [Sidetree Standard Patch action][spa]
 ///
 /// [dsp]: https://identity.foundation/sidetree/spec/v1.0.0/#did-state-patches
 /// [spa]: https://identity.foundation/sidetree/spec/v1.0.0/#standard-patch-actions
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "action")]
 #[serde(rename_all = "kebab-case")]
 pub enum DIDStatePatch {
@@ -76,12 +83,12 @@ pub enum DIDStatePatch {
 /// Specified in [Sidetree §12.1.1 `add-public-keys`][apk].
 ///
 /// [apk]: https://identity.foundation/sidetree/spec/v1.0.0/#add-public-keys
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct PublicKeyEntry {
     /// `id` property
     ///
-    /// Maximum length: 50 in Base64url
+    /// Maximum length: [Sidetree::MAX_ID_LENGTH](crate::sidetree::Sidetree::MAX_ID_LENGTH), in Base64url
     pub id: String,
 
     /// Verification method type
@@ -89,7 +96,8 @@ pub struct PublicKeyEntry {
 
     /// Verification method controller (DID)
     ///
-    /// Maximum length may be set in [Sidetree::MAX_CONTROLLER_LENGTH].
+    /// Allowed only if [Sidetree::ALLOW_CONTROLLER](crate::sidetree::Sidetree::ALLOW_CONTROLLER),
+    /// which ION does not. Maximum length may be set in [Sidetree::MAX_CONTROLLER_LENGTH](crate::sidetree::Sidetree::MAX_CONTROLLER_LENGTH).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub controller: Option<String>,
 
@@ -102,6 +110,7 @@ pub struct PublicKeyEntry {
     /// Defined in [DID Core](https://www.w3.org/TR/did-core/#verification-relationships).
     ///
     /// Corresponds to [`proofPurpose`](https://www.w3.org/TR/did-core/#verification-relationships) in VC Data Model.
+    #[serde(default)]
     pub purposes: Vec<VerificationRelationship>,
 }
 
@@ -115,6 +124,9 @@ pub struct PublicKeyEntry {
 /// [vmm]: https://www.w3.org/TR/did-core/#verification-methods
 pub const VERIFICATION_METHOD_TYPE: &str = "JsonWebSignature2020";
 
+/// Public key entry id for the verification method of the Create operation
+pub const VERIFICATION_METHOD_ID: &str = "key-1";
+
 impl TryFrom<JWK> for PublicKeyEntry {
     type Error = Error;
 
@@ -122,7 +134,7 @@ impl TryFrom<JWK> for PublicKeyEntry {
         let public_key_jwk = PublicKeyJwk::try_from(jwk.to_public()).context("Convert key")?;
         let public_key = PublicKey::PublicKeyJwk(public_key_jwk);
         Ok(PublicKeyEntry {
-            id: "TODO".to_string(),
+            id: VERIFICATION_METHOD_ID.to_string(),
             r#type: VERIFICATION_METHOD_TYPE.to_string(),
             controller: None,
             public_key,
@@ -137,11 +149,43 @@ impl TryFrom<JWK> for PublicKeyEntry {
     }
 }
 
+impl PublicKeyEntry {
+    /// Construct a public key entry from a DID document verification method, for the given
+    /// verification relationships.
+    ///
+    /// The verification method must have `publicKeyJwk` or `publicKeyMultibase`; its controller
+    /// is implied by the DID.
+    pub fn from_verification_method(
+        vmm: VerificationMethodMap,
+        purposes: Vec<VerificationRelationship>,
+    ) -> Result<Self, Error> {
+        let public_key = match (vmm.public_key_jwk, vmm.public_key_multibase) {
+            (Some(jwk), None) => PublicKey::PublicKeyJwk(
+                PublicKeyJwk::try_from(jwk.to_public()).context("Convert key")?,
+            ),
+            (None, Some(multibase)) => PublicKey::PublicKeyMultibase(multibase),
+            _ => {
+                return Err(anyhow!(
+                    "Verification method must have either publicKeyJwk or publicKeyMultibase"
+                ))
+            }
+        };
+        Ok(Self {
+            id: id_fragment(vmm.id),
+            r#type: vmm.type_,
+            controller: None,
+            public_key,
+            purposes,
+        })
+    }
+}
+
 /// Public Key JWK (JSON Web Key)
 ///
 /// Wraps [ssi_jwk::JWK]
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PublicKeyJwk {
+    #[serde(flatten)]
     jwk: Value,
 }
 
@@ -149,13 +193,20 @@ impl TryFrom<JWK> for PublicKeyJwk {
     type Error = PublicKeyJwkFromJWKError;
     fn try_from(jwk: JWK) -> Result<Self, Self::Error> {
         let jwk_value = serde_json::to_value(jwk).map_err(PublicKeyJwkFromJWKError::ToValue)?;
-        // if jwk_value.get("d").is_some() {
-        //     return Err(PublicKeyJwkFromJWKError::PrivateKeyParameters);
-        // };
+        if jwk_value.get("d").is_some() {
+            return Err(PublicKeyJwkFromJWKError::PrivateKeyParameters);
+        };
         Ok(Self { jwk: jwk_value })
     }
 }
 
+impl TryFrom<PublicKeyJwk> for JWK {
+    type Error = serde_json::Error;
+    fn try_from(pkjwk: PublicKeyJwk) -> Result<Self, Self::Error> {
+        serde_json::from_value(pkjwk.jwk)
+    }
+}
+
 /// Public key as JWK or Multibase
 ///
 /// Property of a public key / verification method containing public key data,
@@ -164,7 +215,7 @@ impl TryFrom<JWK> for PublicKeyJwk {
 /// per [Sidetree §12.1.1 `add-public-keys`: Step 4][apk].
 ///
 /// [apk]: https://identity.foundation/sidetree/spec/v1.0.0/#add-public-keys
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum PublicKey {
     /// [`publicKeyJwk`](https://www.w3.org/TR/did-core/#dfn-publickeyjwk) as defined in DID Core.
@@ -174,7 +225,8 @@ pub enum PublicKey {
 
     /// [`publicKeyMultibase`](https://www.w3.org/TR/did-core/#dfn-publickeymultibase) as defined in DID Core.
     ///
-    /// Maximum length may be set in [Sidetree::MAX_PKMB_LENGTH].
+    /// Allowed only if [Sidetree::ALLOW_PKMB](crate::sidetree::Sidetree::ALLOW_PKMB), which ION
+    /// does not. Maximum length may be set in [Sidetree::MAX_PKMB_LENGTH](crate::sidetree::Sidetree::MAX_PKMB_LENGTH).
     PublicKeyMultibase(String),
 }
 
@@ -186,27 +238,81 @@ pub enum PublicKey {
 /// Specified in [Sidetree §12.1.3 `add-services`][as].
 ///
 /// [as]: https://identity.foundation/sidetree/spec/v1.0.0/#add-services
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ServiceEndpointEntry {
     /// `id` property
     ///
-    /// Maximum length: 50 in Base64Url
+    /// Maximum length: [Sidetree::MAX_ID_LENGTH](crate::sidetree::Sidetree::MAX_ID_LENGTH), in Base64url
     pub id: String,
 
     /// Service type
     ///
-    /// Maximum length: 30 in Base64Url
+    /// Maximum length: [Sidetree::MAX_SERVICE_TYPE_LENGTH](crate::sidetree::Sidetree::MAX_SERVICE_TYPE_LENGTH)
     pub r#type: String,
 
     /// Service endpoint URL or object
     pub service_endpoint: ServiceEndpoint,
 }
 
+impl From<ServiceEndpointEntry> for Service {
+    fn from(entry: ServiceEndpointEntry) -> Self {
+        Service {
+            id: format!("#{}", entry.id),
+            type_: OneOrMany::One(entry.r#type),
+            service_endpoint: Some(OneOrMany::One(entry.service_endpoint)),
+            property_set: None,
+        }
+    }
+}
+
+/// Convert a DID document service to a Sidetree service entry.
+///
+/// Sidetree service entries have a single type and a single endpoint, and no other properties.
+impl TryFrom<Service> for ServiceEndpointEntry {
+    type Error = Error;
+
+    fn try_from(service: Service) -> Result<Self, Self::Error> {
+        if service.property_set.is_some_and(|props| !props.is_empty()) {
+            return Err(anyhow!(
+                "Sidetree services cannot have additional properties"
+            ));
+        }
+        let id = id_fragment(service.id);
+        let r#type = match service.type_ {
+            OneOrMany::One(type_) => type_,
+            OneOrMany::Many(mut types) if types.len() == 1 => types.remove(0),
+            OneOrMany::Many(_) => return Err(anyhow!("Sidetree services have a single type")),
+        };
+        let service_endpoint = match service.service_endpoint {
+            Some(OneOrMany::One(endpoint)) => endpoint,
+            Some(OneOrMany::Many(mut endpoints)) if endpoints.len() == 1 => endpoints.remove(0),
+            Some(OneOrMany::Many(_)) => {
+                return Err(anyhow!("Sidetree services have a single endpoint"))
+            }
+            None => return Err(anyhow!("Missing service endpoint")),
+        };
+        Ok(Self {
+            id,
+            r#type,
+            service_endpoint,
+        })
+    }
+}
+
+impl TryFrom<TypedService> for ServiceEndpointEntry {
+    type Error = Error;
+
+    fn try_from(service: TypedService) -> Result<Self, Self::Error> {
+        Self::try_from(Service::from(service))
+    }
+}
+
 /// DID PKI metadata state
 ///
 /// Used by the [`replace`](DIDStatePatch::Replace) DID state patch.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 pub struct DocumentState {
     /// Public key entries
@@ -218,3 +324,661 @@ pub struct DocumentState {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub services: Option<Vec<ServiceEndpointEntry>>,
 }
+
+/// Convert a DID document to the document state it would have in a Sidetree-based DID.
+///
+/// Only verification methods embedded in the document and services are represented in Sidetree
+/// document state; documents with other properties, such as `controller` or `alsoKnownAs`, are
+/// rejected.
+impl TryFrom<&Document> for DocumentState {
+    type Error = Error;
+
+    fn try_from(doc: &Document) -> Result<Self, Self::Error> {
+        if doc.controller.is_some()
+            || doc.also_known_as.is_some()
+            || doc
+                .property_set
+                .as_ref()
+                .is_some_and(|props| !props.is_empty())
+        {
+            return Err(anyhow!(
+                "Sidetree document state has only public keys and services"
+            ));
+        }
+        let relationships = VerificationRelationship::ALL
+            .iter()
+            .flat_map(|rel| doc.get_relationship(*rel));
+        let mut ids = HashSet::new();
+        let mut public_keys = Vec::new();
+        for vm in doc
+            .verification_method
+            .iter()
+            .flatten()
+            .chain(relationships)
+        {
+            let vmm = match vm {
+                VerificationMethod::Map(vmm) => vmm,
+                VerificationMethod::DIDURL(_) => continue,
+            };
+            let id = vmm.get_id(&doc.id);
+            if !ids.insert(id.clone()) {
+                continue;
+            }
+            let purposes = VerificationRelationship::ALL
+                .iter()
+                .copied()
+                .filter(|rel| {
+                    doc.verification_methods_for(*rel)
+                        .iter()
+                        .any(|vmm| vmm.get_id(&doc.id) == id)
+                })
+                .collect();
+            let public_key = PublicKeyEntry::from_verification_method(vmm.clone(), purposes)
+                .with_context(|| format!("Convert verification method {id}"))?;
+            public_keys.push(public_key);
+        }
+        let services = doc
+            .service
+            .iter()
+            .flatten()
+            .map(|service| {
+                ServiceEndpointEntry::try_from(service.clone())
+                    .with_context(|| format!("Convert service {}", service.id))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Self {
+            public_keys: Some(public_keys),
+            services: Some(services),
+        })
+    }
+}
+
+/// Convert a DID document operation, as requested in a [DID Update](ssi_dids::DIDUpdate), to
+/// a DID state patch.
+///
+/// Adding or removing arbitrary DID document properties is not supported, since Sidetree
+/// document state only has public keys and services.
+impl TryFrom<DIDDocumentOperation> for DIDStatePatch {
+    type Error = Error;
+
+    fn try_from(operation: DIDDocumentOperation) -> Result<Self, Self::Error> {
+        Ok(match operation {
+            DIDDocumentOperation::SetDidDocument(doc) => DIDStatePatch::Replace {
+                document: DocumentState::try_from(&doc)?,
+            },
+            DIDDocumentOperation::SetVerificationMethod { vmm, purposes } => {
+                DIDStatePatch::AddPublicKeys {
+                    public_keys: vec![PublicKeyEntry::from_verification_method(vmm, purposes)?],
+                }
+            }
+            DIDDocumentOperation::SetService(service) => DIDStatePatch::AddServices {
+                services: vec![ServiceEndpointEntry::try_from(service)?],
+            },
+            DIDDocumentOperation::RemoveVerificationMethod(id) => DIDStatePatch::RemovePublicKeys {
+                ids: vec![id_fragment(id)],
+            },
+            DIDDocumentOperation::RemoveService(id) => DIDStatePatch::RemoveServices {
+                ids: vec![id_fragment(id)],
+            },
+            DIDDocumentOperation::AddToDidDocument(_)
+            | DIDDocumentOperation::RemoveFromDidDocument(_) => {
+                return Err(anyhow!(
+                    "Sidetree DIDs do not support adding or removing DID document properties"
+                ))
+            }
+        })
+    }
+}
+
+/// Get the id of a public key or service entry from a DID URL or relative reference, i.e. its
+/// fragment.
+fn id_fragment(id: String) -> String {
+    match id.split_once('#') {
+        Some((_, fragment)) => fragment.to_string(),
+        None => id,
+    }
+}
+
+impl DIDStatePatch {
+    /// Apply the patch to a document state, per the [Sidetree Standard Patch actions][spa].
+    ///
+    /// - `add-public-keys` and `add-services` add entries in order. An entry with the id of an
+    ///   existing entry overwrites it in place, as in ION. Ids must be unique within the patch.
+    /// - `remove-public-keys` and `remove-services` remove the entries with the given ids.
+    /// - `replace` replaces the document state, which must have unique ids.
+    /// - `ietf-json-patch` applies [RFC 6902](https://datatracker.ietf.org/doc/html/rfc6902)
+    ///   operations to the JSON of the document state. The result must be a valid document
+    ///   state.
+    ///
+    /// On error, the document state is left unchanged. To apply the patches of a delta, which
+    /// must succeed or fail as a whole, use [DocumentState::apply_patches].
+    ///
+    /// [spa]: https://identity.foundation/sidetree/spec/v1.0.0/#standard-patch-actions
+    pub fn apply(&self, state: &mut DocumentState) -> Result<(), DIDStatePatchError> {
+        match self {
+            DIDStatePatch::AddPublicKeys { public_keys } => {
+                check_unique_ids(public_keys.iter().map(|key| &key.id))
+                    .map_err(DIDStatePatchError::DuplicatePublicKeyId)?;
+                let state_keys = state.public_keys.get_or_insert_with(Vec::new);
+                for public_key in public_keys {
+                    match state_keys.iter_mut().find(|key| key.id == public_key.id) {
+                        Some(key) => *key = public_key.clone(),
+                        None => state_keys.push(public_key.clone()),
+                    }
+                }
+            }
+            DIDStatePatch::RemovePublicKeys { ids } => {
+                if let Some(state_keys) = &mut state.public_keys {
+                    state_keys.retain(|key| !ids.contains(&key.id));
+                }
+            }
+            DIDStatePatch::AddServices { services } => {
+                check_unique_ids(services.iter().map(|service| &service.id))
+                    .map_err(DIDStatePatchError::DuplicateServiceId)?;
+                let state_services = state.services.get_or_insert_with(Vec::new);
+                for service in services {
+                    match state_services
+                        .iter_mut()
+                        .find(|entry| entry.id == service.id)
+                    {
+                        Some(entry) => *entry = service.clone(),
+                        None => state_services.push(service.clone()),
+                    }
+                }
+            }
+            DIDStatePatch::RemoveServices { ids } => {
+                if let Some(state_services) = &mut state.services {
+                    state_services.retain(|service| !ids.contains(&service.id));
+                }
+            }
+            DIDStatePatch::Replace { document } => {
+                document.check_unique_ids()?;
+                *state = document.clone();
+            }
+            DIDStatePatch::IetfJsonPatch { patches } => {
+                let mut value =
+                    serde_json::to_value(&*state).map_err(DIDStatePatchError::ToValue)?;
+                json_patch::patch(&mut value, patches)?;
+                let document: DocumentState = serde_json::from_value(value)
+                    .map_err(DIDStatePatchError::InvalidDocumentState)?;
+                document.check_unique_ids()?;
+                *state = document;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that ids are unique, returning the first duplicate id otherwise.
+fn check_unique_ids<'a>(ids: impl Iterator<Item = &'a String>) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            return Err(id.clone());
+        }
+    }
+    Ok(())
+}
+
+impl DocumentState {
+    /// Apply the patches of a delta, returning the resulting document state.
+    ///
+    /// Per Sidetree, a failed patch voids the whole delta: if any patch fails, the error is
+    /// returned and none of the patches take effect.
+    pub fn apply_patches(
+        &self,
+        patches: &[DIDStatePatch],
+    ) -> Result<DocumentState, DIDStatePatchError> {
+        let mut state = self.clone();
+        for patch in patches {
+            patch.apply(&mut state)?;
+        }
+        Ok(state)
+    }
+
+    fn check_unique_ids(&self) -> Result<(), DIDStatePatchError> {
+        check_unique_ids(self.public_keys.iter().flatten().map(|key| &key.id))
+            .map_err(DIDStatePatchError::DuplicatePublicKeyId)?;
+        check_unique_ids(self.services.iter().flatten().map(|service| &service.id))
+            .map_err(DIDStatePatchError::DuplicateServiceId)
+    }
+
+    /// Construct the DID document for the document state of a DID.
+    ///
+    /// Following ION, the DID is set as the JSON-LD `@base`, and public keys and services have
+    /// ids relative to the DID.
+    pub fn to_document(&self, did: &str) -> Result<Document, Error> {
+        let mut doc = Document::new(did);
+        let mut base = BTreeMap::new();
+        base.insert("@base".to_string(), Value::String(did.to_string()));
+        doc.context = Some(OneOrMany::Many(vec![
+            JsonLdContext::URI(DEFAULT_CONTEXT.to_string()),
+            JsonLdContext::Object(base),
+        ]));
+        for public_key in self.public_keys.iter().flatten() {
+            let id = format!("#{}", public_key.id);
+            let (public_key_jwk, public_key_multibase) = match &public_key.public_key {
+                PublicKey::PublicKeyJwk(pkjwk) => {
+                    let jwk = JWK::try_from(pkjwk.clone())
+                        .with_context(|| format!("Convert public key {id}"))?;
+                    (Some(jwk), None)
+                }
+                PublicKey::PublicKeyMultibase(multibase) => (None, Some(multibase.clone())),
+            };
+            let vmm = VerificationMethodMap {
+                id: id.clone(),
+                type_: public_key.r#type.clone(),
+                controller: public_key
+                    .controller
+                    .clone()
+                    .unwrap_or_else(|| did.to_string()),
+                public_key_jwk,
+                public_key_multibase,
+                property_set: None,
+            };
+            doc.verification_method
+                .get_or_insert_with(Vec::new)
+                .push(VerificationMethod::Map(vmm));
+            for purpose in &public_key.purposes {
+                let relationship = match purpose {
+                    VerificationRelationship::Authentication => &mut doc.authentication,
+                    VerificationRelationship::AssertionMethod => &mut doc.assertion_method,
+                    VerificationRelationship::KeyAgreement => &mut doc.key_agreement,
+                    VerificationRelationship::CapabilityInvocation => {
+                        &mut doc.capability_invocation
+                    }
+                    VerificationRelationship::CapabilityDelegation => {
+                        &mut doc.capability_delegation
+                    }
+                };
+                relationship
+                    .get_or_insert_with(Vec::new)
+                    .push(VerificationMethod::DIDURL(id.clone()));
+            }
+        }
+        let services: Vec<Service> = self
+            .services
+            .iter()
+            .flatten()
+            .cloned()
+            .map(Service::from)
+            .collect();
+        if !services.is_empty() {
+            doc.service = Some(services);
+        }
+        Ok(doc)
+    }
+
+    /// Compute the DID state patches that turn this document state into `desired`.
+    ///
+    /// Entries are matched by id. Entries missing from `desired` are removed with
+    /// [`remove-public-keys`](DIDStatePatch::RemovePublicKeys) and
+    /// [`remove-services`](DIDStatePatch::RemoveServices); new and changed entries are added with
+    /// [`add-public-keys`](DIDStatePatch::AddPublicKeys) and
+    /// [`add-services`](DIDStatePatch::AddServices), which overwrite entries with the same id.
+    /// Overwritten entries keep their position and new entries are appended, so these patches
+    /// cannot reorder entries or distinguish an empty list from a missing one. If they do not
+    /// reproduce `desired` exactly, or a single [`replace`](DIDStatePatch::Replace) patch is
+    /// smaller, the replace patch is returned instead. No patches are returned if the states are
+    /// already equal.
+    ///
+    /// `desired` must not have duplicate public key or service ids.
+    pub fn diff(&self, desired: &DocumentState) -> Result<Vec<DIDStatePatch>, DIDStatePatchError> {
+        desired.check_unique_ids()?;
+        if self == desired {
+            return Ok(Vec::new());
+        }
+        let current_keys = self.public_keys.as_deref().unwrap_or_default();
+        let desired_keys = desired.public_keys.as_deref().unwrap_or_default();
+        let current_services = self.services.as_deref().unwrap_or_default();
+        let desired_services = desired.services.as_deref().unwrap_or_default();
+        let replace = vec![DIDStatePatch::Replace {
+            document: desired.clone(),
+        }];
+
+        let (remove_keys, add_keys) = diff_entries(current_keys, desired_keys, |key| &key.id);
+        let (remove_services, add_services) =
+            diff_entries(current_services, desired_services, |service| &service.id);
+        let mut patches = Vec::new();
+        if !remove_keys.is_empty() {
+            patches.push(DIDStatePatch::RemovePublicKeys { ids: remove_keys });
+        }
+        if !add_keys.is_empty() {
+            patches.push(DIDStatePatch::AddPublicKeys {
+                public_keys: add_keys,
+            });
+        }
+        if !remove_services.is_empty() {
+            patches.push(DIDStatePatch::RemoveServices {
+                ids: remove_services,
+            });
+        }
+        if !add_services.is_empty() {
+            patches.push(DIDStatePatch::AddServices {
+                services: add_services,
+            });
+        }
+        if self.apply_patches(&patches).ok().as_ref() != Some(desired) {
+            return Ok(replace);
+        }
+        let size = |patches: &[DIDStatePatch]| {
+            serde_jcs::to_string(patches).map_or(usize::MAX, |string| string.len())
+        };
+        if size(&replace) < size(&patches) {
+            return Ok(replace);
+        }
+        Ok(patches)
+    }
+}
+
+/// Get the ids of entries to remove, and the entries to add or overwrite.
+fn diff_entries<T: Clone + PartialEq>(
+    current: &[T],
+    desired: &[T],
+    id: impl Fn(&T) -> &String,
+) -> (Vec<String>, Vec<T>) {
+    let desired_ids: HashSet<&String> = desired.iter().map(&id).collect();
+    let mut removed = HashSet::new();
+    let remove = current
+        .iter()
+        .map(&id)
+        .filter(|current_id| !desired_ids.contains(current_id) && removed.insert(*current_id))
+        .cloned()
+        .collect();
+    let add = desired
+        .iter()
+        .filter(|entry| !current.contains(entry))
+        .cloned()
+        .collect();
+    (remove, add)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(id: &str, x: &str) -> PublicKeyEntry {
+        serde_json::from_value(json!({
+            "id": id,
+            "type": VERIFICATION_METHOD_TYPE,
+            "publicKeyJwk": {"kty": "EC", "crv": "secp256k1", "x": x, "y": "y"},
+            "purposes": ["authentication"],
+        }))
+        .unwrap()
+    }
+
+    fn service(id: &str, endpoint: &str) -> ServiceEndpointEntry {
+        serde_json::from_value(json!({
+            "id": id,
+            "type": "LinkedDomains",
+            "serviceEndpoint": endpoint,
+        }))
+        .unwrap()
+    }
+
+    fn state(keys: &[(&str, &str)], services: &[(&str, &str)]) -> DocumentState {
+        DocumentState {
+            public_keys: Some(keys.iter().map(|(id, x)| key(id, x)).collect()),
+            services: Some(
+                services
+                    .iter()
+                    .map(|(id, endpoint)| service(id, endpoint))
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn add_overwrites_in_place() {
+        let current = state(
+            &[("a", "1"), ("b", "1"), ("c", "1")],
+            &[("s1", "https://a.example"), ("s2", "https://b.example")],
+        );
+        let patches = vec![
+            DIDStatePatch::AddPublicKeys {
+                public_keys: vec![key("b", "2"), key("d", "1")],
+            },
+            DIDStatePatch::AddServices {
+                services: vec![service("s1", "https://c.example")],
+            },
+        ];
+        let result = current.apply_patches(&patches).unwrap();
+        assert_eq!(
+            result,
+            state(
+                &[("a", "1"), ("b", "2"), ("c", "1"), ("d", "1")],
+                &[("s1", "https://c.example"), ("s2", "https://b.example")],
+            )
+        );
+    }
+
+    #[test]
+    fn add_to_empty_state() {
+        let patch = DIDStatePatch::AddServices {
+            services: vec![service("s1", "https://a.example")],
+        };
+        let result = DocumentState::default().apply_patches(&[patch]).unwrap();
+        assert_eq!(
+            result,
+            DocumentState {
+                public_keys: None,
+                services: Some(vec![service("s1", "https://a.example")]),
+            }
+        );
+    }
+
+    #[test]
+    fn remove_missing_id_is_ignored() {
+        let current = state(&[("a", "1"), ("b", "1")], &[("s1", "https://a.example")]);
+        let patches = vec![
+            DIDStatePatch::RemovePublicKeys {
+                ids: vec!["b".to_string(), "missing".to_string()],
+            },
+            DIDStatePatch::RemoveServices {
+                ids: vec!["missing".to_string()],
+            },
+        ];
+        let result = current.apply_patches(&patches).unwrap();
+        assert_eq!(result, state(&[("a", "1")], &[("s1", "https://a.example")]));
+        assert_eq!(
+            DocumentState::default().apply_patches(&patches).unwrap(),
+            DocumentState::default()
+        );
+    }
+
+    #[test]
+    fn duplicate_ids_in_patch_fail() {
+        let patch = DIDStatePatch::AddPublicKeys {
+            public_keys: vec![key("a", "1"), key("a", "2")],
+        };
+        assert!(matches!(
+            DocumentState::default().apply_patches(&[patch]),
+            Err(DIDStatePatchError::DuplicatePublicKeyId(id)) if id == "a"
+        ));
+        let document = state(
+            &[],
+            &[("s", "https://a.example"), ("s", "https://b.example")],
+        );
+        assert!(matches!(
+            DocumentState::default().apply_patches(&[DIDStatePatch::Replace { document }]),
+            Err(DIDStatePatchError::DuplicateServiceId(id)) if id == "s"
+        ));
+    }
+
+    #[test]
+    fn failed_patch_voids_delta() {
+        let current = state(&[("a", "1")], &[]);
+        let patches = vec![
+            DIDStatePatch::AddPublicKeys {
+                public_keys: vec![key("b", "1")],
+            },
+            DIDStatePatch::RemovePublicKeys {
+                ids: vec!["a".to_string()],
+            },
+            DIDStatePatch::AddServices {
+                services: vec![
+                    service("s", "https://a.example"),
+                    service("s", "https://b.example"),
+                ],
+            },
+        ];
+        assert!(current.apply_patches(&patches).is_err());
+        assert_eq!(current, state(&[("a", "1")], &[]));
+
+        let mut single = current.clone();
+        assert!(patches[2].apply(&mut single).is_err());
+        assert_eq!(single, current);
+    }
+
+    #[test]
+    fn ietf_json_patch() {
+        let current = state(&[("a", "1")], &[("s1", "https://a.example")]);
+        let patch: DIDStatePatch = serde_json::from_value(json!({
+            "action": "ietf-json-patch",
+            "patches": [
+                {"op": "replace", "path": "/services/0/serviceEndpoint", "value": "https://b.example"},
+                {"op": "remove", "path": "/publicKeys/0"},
+            ],
+        }))
+        .unwrap();
+        let result = current.apply_patches(&[patch]).unwrap();
+        assert_eq!(result, state(&[], &[("s1", "https://b.example")]));
+
+        let invalid: DIDStatePatch = serde_json::from_value(json!({
+            "action": "ietf-json-patch",
+            "patches": [{"op": "add", "path": "/unknown", "value": 1}],
+        }))
+        .unwrap();
+        assert!(matches!(
+            current.apply_patches(&[invalid]),
+            Err(DIDStatePatchError::InvalidDocumentState(_))
+        ));
+        let failing: DIDStatePatch = serde_json::from_value(json!({
+            "action": "ietf-json-patch",
+            "patches": [{"op": "remove", "path": "/publicKeys/5"}],
+        }))
+        .unwrap();
+        assert!(matches!(
+            current.apply_patches(&[failing]),
+            Err(DIDStatePatchError::JsonPatch(_))
+        ));
+    }
+
+    fn assert_round_trip(current: &DocumentState, desired: &DocumentState) -> Vec<DIDStatePatch> {
+        let patches = current.diff(desired).unwrap();
+        assert_eq!(
+            current.apply_patches(&patches).unwrap(),
+            *desired,
+            "patches: {:?}",
+            patches
+        );
+        patches
+    }
+
+    #[test]
+    fn diff_round_trips() {
+        let states = vec![
+            DocumentState::default(),
+            state(&[], &[]),
+            DocumentState {
+                public_keys: Some(vec![]),
+                services: None,
+            },
+            state(&[("a", "1")], &[]),
+            state(&[("a", "1"), ("b", "1")], &[("s1", "https://a.example")]),
+            state(&[("b", "1"), ("a", "1")], &[("s1", "https://a.example")]),
+            state(&[("a", "2"), ("b", "1")], &[("s1", "https://b.example")]),
+            state(&[("a", "1"), ("c", "1"), ("b", "1")], &[]),
+            state(
+                &[("a", "1"), ("b", "1"), ("c", "1"), ("d", "1")],
+                &[("s1", "https://a.example"), ("s2", "https://a.example")],
+            ),
+            state(
+                &[("d", "1"), ("c", "2"), ("b", "1"), ("a", "1")],
+                &[("s2", "https://a.example"), ("s1", "https://a.example")],
+            ),
+        ];
+        for current in &states {
+            for desired in &states {
+                assert_round_trip(current, desired);
+            }
+        }
+    }
+
+    #[test]
+    fn diff_equal_states_is_empty() {
+        let current = state(&[("a", "1")], &[("s1", "https://a.example")]);
+        assert!(current.diff(&current.clone()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn diff_uses_minimal_patches() {
+        let current = state(
+            &[("a", "1"), ("b", "1"), ("c", "1"), ("d", "1")],
+            &[("s1", "https://a.example"), ("s2", "https://b.example")],
+        );
+        let desired = state(
+            &[("a", "1"), ("b", "2"), ("d", "1"), ("e", "1")],
+            &[("s1", "https://a.example")],
+        );
+        let patches = assert_round_trip(&current, &desired);
+        assert_eq!(
+            patches,
+            vec![
+                DIDStatePatch::RemovePublicKeys {
+                    ids: vec!["c".to_string()]
+                },
+                DIDStatePatch::AddPublicKeys {
+                    public_keys: vec![key("b", "2"), key("e", "1")]
+                },
+                DIDStatePatch::RemoveServices {
+                    ids: vec!["s2".to_string()]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_replaces_when_order_changes() {
+        let current = state(&[("a", "1"), ("b", "1"), ("c", "1"), ("d", "1")], &[]);
+        let desired = state(
+            &[("a", "1"), ("e", "1"), ("b", "1"), ("c", "1"), ("d", "1")],
+            &[],
+        );
+        let patches = assert_round_trip(&current, &desired);
+        assert!(matches!(patches[..], [DIDStatePatch::Replace { .. }]));
+
+        let empty = DocumentState {
+            public_keys: Some(vec![]),
+            services: None,
+        };
+        let patches = assert_round_trip(&DocumentState::default(), &empty);
+        assert!(matches!(patches[..], [DIDStatePatch::Replace { .. }]));
+    }
+
+    #[test]
+    fn diff_replaces_duplicate_current_ids() {
+        let current = state(&[("a", "1"), ("a", "1")], &[]);
+        let desired = state(&[("a", "1")], &[]);
+        let patches = assert_round_trip(&current, &desired);
+        assert!(matches!(patches[..], [DIDStatePatch::Replace { .. }]));
+    }
+
+    #[test]
+    fn diff_rejects_duplicate_desired_ids() {
+        let desired = state(&[("a", "1"), ("a", "2")], &[]);
+        assert!(matches!(
+            DocumentState::default().diff(&desired),
+            Err(DIDStatePatchError::DuplicatePublicKeyId(id)) if id == "a"
+        ));
+        let desired = state(
+            &[],
+            &[("s", "https://a.example"), ("s", "https://b.example")],
+        );
+        assert!(matches!(
+            DocumentState::default().diff(&desired),
+            Err(DIDStatePatchError::DuplicateServiceId(id)) if id == "s"
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 use thiserror::Error as ThisError;
 
 #[derive(ThisError, Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum SidetreeError {
     /// Error from [serde_jcs::to_string]
     #[error("Unable to execute JSON Canonicalization Scheme (JCS)")]
@@ -17,3 +18,29 @@ pub enum PublicKeyJwkFromJWKError {
     #[error("Public Key JWK must not contain private key parameters")]
     PrivateKeyParameters,
 }
+
+/// Error [applying][apply] or [computing][diff] DID state patches
+///
+/// Per Sidetree, if any patch of a delta fails, the whole delta is void and the DID state is left
+/// unchanged.
+///
+/// [apply]: crate::delta::patches::DIDStatePatch::apply
+/// [diff]: crate::delta::patches::DocumentState::diff
+#[derive(ThisError, Debug)]
+pub enum DIDStatePatchError {
+    /// A public key id appears more than once in a patch or document state
+    #[error("Duplicate public key id: {0}")]
+    DuplicatePublicKeyId(String),
+    /// A service id appears more than once in a patch or document state
+    #[error("Duplicate service id: {0}")]
+    DuplicateServiceId(String),
+    /// Unable to convert the document state to JSON for an `ietf-json-patch` action
+    #[error("Unable to convert document state to JSON")]
+    ToValue(#[source] serde_json::Error),
+    /// Error applying the operations of an `ietf-json-patch` action
+    #[error("Unable to apply JSON Patch")]
+    JsonPatch(#[from] json_patch::PatchError),
+    /// The result of an `ietf-json-patch` action is not a valid document state
+    #[error("JSON Patch result is not a valid document state")]
+    InvalidDocumentState(#[source] serde_json::Error),
+}
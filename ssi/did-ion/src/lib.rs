@@ -1,9 +1,10 @@
-mod client;
-mod delta;
-mod error;
-mod operation;
-mod sidetree;
-mod suffix_data;
+pub mod client;
+pub mod delta;
+pub mod error;
+pub mod operation;
+pub mod proof_of_work;
+pub mod sidetree;
+pub mod suffix_data;
 
 use client::SidetreeClient;
 use sidetree::Sidetree;
@@ -1,15 +1,36 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{delta::Delta, suffix_data::SuffixData};
+use anyhow::Result;
+
+use crate::{
+    delta::{patches::PublicKeyJwk, Delta},
+    sidetree::Sidetree,
+    suffix_data::SuffixData,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 pub enum Operation {
     Create(CreateOperation),
-    // Update(UpdateOperation),
-    // Recover(RecoverOperation),
-    // Deactivate(DeactivateOperation),
+    Update(UpdateOperation),
+    Recover(RecoverOperation),
+    Deactivate(DeactivateOperation),
+}
+
+impl Operation {
+    /// Get the [DID Suffix](https://identity.foundation/sidetree/spec/v1.0.0/#did-suffix) of the
+    /// DID that the operation is for.
+    ///
+    /// For a Create operation, this is computed from the suffix data.
+    pub fn did_suffix<S: Sidetree>(&self) -> Result<String> {
+        Ok(match self {
+            Operation::Create(op) => S::did_suffix_from_suffix_data(&op.suffix_data)?,
+            Operation::Update(op) => op.did_suffix.clone(),
+            Operation::Recover(op) => op.did_suffix.clone(),
+            Operation::Deactivate(op) => op.did_suffix.clone(),
+        })
+    }
 }
 
 /// Sidetree DID Create operation
@@ -23,3 +44,127 @@ pub struct CreateOperation {
     pub delta: Delta,
     pub suffix_data: SuffixData,
 }
+
+/// Sidetree DID Update operation
+///
+/// ### References
+/// - [Sidetree §11.2 Update](https://identity.foundation/sidetree/spec/v1.0.0/#update)
+/// - [Sidetree REST API §1.2.2 Update](https://identity.foundation/sidetree/api/#update)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOperation {
+    /// [DID Suffix](https://identity.foundation/sidetree/spec/v1.0.0/#did-suffix) of the DID to
+    /// update
+    pub did_suffix: String,
+
+    /// [Reveal value](https://identity.foundation/sidetree/spec/v1.0.0/#reveal-value) of the
+    /// current update key
+    pub reveal_value: String,
+
+    /// Update Operation Delta Object
+    pub delta: Delta,
+
+    /// Compact JWS of [UpdateClaims], signed with the current update key
+    pub signed_data: String,
+}
+
+/// Payload of the [signed data](UpdateOperation::signed_data) of an Update operation
+///
+/// [Sidetree §11.2 Update - Update Operation Signed Data Object][data]
+///
+/// [data]: https://identity.foundation/sidetree/spec/v1.0.0/#update-signed-data-object
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateClaims {
+    /// Current update key, matching the update commitment
+    pub update_key: PublicKeyJwk,
+
+    /// [Hash](crate::sidetree::Sidetree::hash) of the canonicalized Update Operation Delta
+    /// Object
+    pub delta_hash: String,
+}
+
+/// Sidetree DID Recover operation
+///
+/// ### References
+/// - [Sidetree §11.3 Recover](https://identity.foundation/sidetree/spec/v1.0.0/#recover)
+/// - [Sidetree REST API §1.2.3 Recover](https://identity.foundation/sidetree/api/#recover)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverOperation {
+    /// [DID Suffix](https://identity.foundation/sidetree/spec/v1.0.0/#did-suffix) of the DID to
+    /// recover
+    pub did_suffix: String,
+
+    /// [Reveal value](https://identity.foundation/sidetree/spec/v1.0.0/#reveal-value) of the
+    /// current recovery key
+    pub reveal_value: String,
+
+    /// Recover Operation Delta Object
+    pub delta: Delta,
+
+    /// Compact JWS of [RecoveryClaims], signed with the current recovery key
+    pub signed_data: String,
+}
+
+/// Payload of the [signed data](RecoverOperation::signed_data) of a Recover operation
+///
+/// [Sidetree §11.3 Recover - Recover Operation Signed Data Object][data]
+///
+/// [data]: https://identity.foundation/sidetree/spec/v1.0.0/#recovery-signed-data-object
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryClaims {
+    /// Current recovery key, matching the recovery commitment
+    pub recovery_key: PublicKeyJwk,
+
+    /// [Recovery commitment](https://identity.foundation/sidetree/spec/v1.0.0/#recovery-commitment)
+    /// for the next Recover or Deactivate operation
+    pub recovery_commitment: String,
+
+    /// [Hash](crate::sidetree::Sidetree::hash) of the canonicalized Recover Operation Delta
+    /// Object
+    pub delta_hash: String,
+
+    /// Anchor Origin
+    ///
+    /// Implementation-defined identifier for most recent anchor for the DID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchor_origin: Option<String>,
+}
+
+/// Sidetree DID Deactivate operation
+///
+/// ### References
+/// - [Sidetree §11.4 Deactivate](https://identity.foundation/sidetree/spec/v1.0.0/#deactivate)
+/// - [Sidetree REST API §1.2.4 Deactivate](https://identity.foundation/sidetree/api/#deactivate)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeactivateOperation {
+    /// [DID Suffix](https://identity.foundation/sidetree/spec/v1.0.0/#did-suffix) of the DID to
+    /// deactivate
+    pub did_suffix: String,
+
+    /// [Reveal value](https://identity.foundation/sidetree/spec/v1.0.0/#reveal-value) of the
+    /// current recovery key
+    pub reveal_value: String,
+
+    /// Compact JWS of [DeactivateClaims], signed with the current recovery key
+    pub signed_data: String,
+}
+
+/// Payload of the [signed data](DeactivateOperation::signed_data) of a Deactivate operation
+///
+/// [Sidetree §11.4 Deactivate - Deactivate Operation Signed Data Object][data]
+///
+/// [data]: https://identity.foundation/sidetree/spec/v1.0.0/#deactivate-signed-data-object
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeactivateClaims {
+    /// [DID Suffix](https://identity.foundation/sidetree/spec/v1.0.0/#did-suffix) of the DID to
+    /// deactivate
+    pub did_suffix: String,
+
+    /// Current recovery key, matching the recovery commitment
+    pub recovery_key: PublicKeyJwk,
+}
@@ -0,0 +1,233 @@
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::Rng;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// HTTP header for the [challenge nonce](ProofOfWorkChallenge::challenge_nonce) of a solved
+/// challenge
+pub const CHALLENGE_NONCE_HEADER: &str = "Challenge-Nonce";
+
+/// HTTP header for the [answer nonce](ProofOfWorkAnswer::answer_nonce) of a solved challenge
+pub const ANSWER_NONCE_HEADER: &str = "Answer-Nonce";
+
+/// HTTP header for the [answer hash](ProofOfWorkAnswer::answer_hash) of a solved challenge
+pub const ANSWER_HASH_HEADER: &str = "Answer-Hash";
+
+/// Argon2id memory cost in KiB, as used by ION nodes
+const MEMORY_COST: u32 = 1000;
+
+/// Argon2id time cost (iterations), as used by ION nodes
+const TIME_COST: u32 = 1;
+
+/// Argon2id degree of parallelism, as used by ION nodes
+const PARALLELISM: u32 = 1;
+
+/// Length in bytes of the answer hash
+const HASH_LENGTH: usize = 32;
+
+/// Maximum time spent solving a challenge, however long the node accepts answers
+pub const MAX_SOLVE_DURATION: Duration = Duration::from_secs(10 * 60);
+
+/// Proof-of-work challenge from an ION node's `proof-of-work-challenge` endpoint
+///
+/// Public ION nodes accept an operation only with a solved challenge, to limit spam.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofOfWorkChallenge {
+    /// Hex-encoded nonce, used as the Argon2id salt
+    pub challenge_nonce: String,
+
+    /// Hex-encoded target difficulty: an answer hash must not be greater than this hash.
+    pub largest_allowed_hash: String,
+
+    /// Time after which the node no longer accepts answers to the challenge
+    pub valid_duration_in_minutes: u64,
+}
+
+/// Answer to a [ProofOfWorkChallenge] for a request body
+#[derive(Debug, Clone)]
+pub struct ProofOfWorkAnswer {
+    /// Hex-encoded answer nonce, sent as the [ANSWER_NONCE_HEADER]
+    pub answer_nonce: String,
+
+    /// Hex-encoded answer hash, sent as the [ANSWER_HASH_HEADER]
+    pub answer_hash: String,
+}
+
+impl ProofOfWorkChallenge {
+    /// Fetch a challenge from a challenge endpoint URL.
+    pub async fn fetch(client: &reqwest::Client, url: &str) -> Result<Self> {
+        let resp = client
+            .get(url)
+            .send()
+            .await
+            .context("Send proof-of-work challenge request")?;
+        let status = resp.status();
+        let body = resp
+            .text()
+            .await
+            .context("Read proof-of-work challenge response")?;
+        if !status.is_success() {
+            bail!("Sidetree node returned error for proof-of-work challenge ({status}): {body}");
+        }
+        serde_json::from_str(&body).context("Parse proof-of-work challenge")
+    }
+
+    /// Solve the challenge for a request body on a blocking thread, as by
+    /// [solve_blocking](Self::solve_blocking).
+    pub async fn solve(&self, request_body: &str) -> Result<ProofOfWorkAnswer> {
+        let challenge = self.clone();
+        let request_body = request_body.to_string();
+        tokio::task::spawn_blocking(move || challenge.solve_blocking(&request_body))
+            .await
+            .context("Join proof-of-work task")?
+    }
+
+    /// Solve the challenge for a request body, by trying random answer nonces until the answer
+    /// hash meets the target difficulty.
+    ///
+    /// Fails if the challenge expires, or [MAX_SOLVE_DURATION] passes, before an answer is
+    /// found. This is CPU-bound; in async code, use [solve](Self::solve).
+    pub fn solve_blocking(&self, request_body: &str) -> Result<ProofOfWorkAnswer> {
+        let largest_allowed_hash = self.largest_allowed_hash.to_ascii_lowercase();
+        ensure!(
+            largest_allowed_hash.len() == HASH_LENGTH * 2
+                && largest_allowed_hash.bytes().all(|b| b.is_ascii_hexdigit()),
+            "Invalid largest allowed hash: {}",
+            self.largest_allowed_hash
+        );
+        let valid_duration = Duration::from_secs(self.valid_duration_in_minutes.saturating_mul(60));
+        let max_duration = valid_duration.min(MAX_SOLVE_DURATION);
+        let start = Instant::now();
+        let mut rng = rand::thread_rng();
+        loop {
+            ensure!(
+                start.elapsed() < max_duration,
+                "No proof-of-work answer found within {} seconds",
+                max_duration.as_secs()
+            );
+            let random_bytes: [u8; 16] = rng.gen();
+            let answer_nonce = to_hex(to_hex(&random_bytes).as_bytes());
+            let answer_hash = answer_hash(&self.challenge_nonce, &answer_nonce, request_body)?;
+            if answer_hash <= largest_allowed_hash {
+                return Ok(ProofOfWorkAnswer {
+                    answer_nonce,
+                    answer_hash,
+                });
+            }
+        }
+    }
+}
+
+/// Compute the hex-encoded answer hash for a challenge nonce, answer nonce and request body.
+///
+/// The hash is Argon2id over the decoded answer nonce followed by the request body, salted with
+/// the decoded challenge nonce.
+pub fn answer_hash(
+    challenge_nonce: &str,
+    answer_nonce: &str,
+    request_body: &str,
+) -> Result<String> {
+    let salt = from_hex(challenge_nonce).context("Decode challenge nonce")?;
+    let mut password = from_hex(answer_nonce).context("Decode answer nonce")?;
+    password.extend_from_slice(request_body.as_bytes());
+    let params = Params::new(MEMORY_COST, TIME_COST, PARALLELISM, Some(HASH_LENGTH))
+        .map_err(|err| anyhow!("Argon2id parameters: {err}"))?;
+    let mut hash = [0u8; HASH_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(&password, &salt, &mut hash)
+        .map_err(|err| anyhow!("Argon2id: {err}"))?;
+    Ok(to_hex(&hash))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(string: &str) -> Result<Vec<u8>> {
+    ensure!(
+        string.len().is_multiple_of(2) && string.bytes().all(|b| b.is_ascii_hexdigit()),
+        "Invalid hex string"
+    );
+    (0..string.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&string[i..i + 2], 16).context("Invalid hex string"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHALLENGE_NONCE: &str = "d1f6c8b0a7a35c7de1f56f3c0c1d2e3f";
+    const REQUEST_BODY: &str = r#"{"type":"create","suffixData":{}}"#;
+
+    fn challenge(
+        largest_allowed_hash: &str,
+        valid_duration_in_minutes: u64,
+    ) -> ProofOfWorkChallenge {
+        ProofOfWorkChallenge {
+            challenge_nonce: CHALLENGE_NONCE.to_string(),
+            largest_allowed_hash: largest_allowed_hash.to_string(),
+            valid_duration_in_minutes,
+        }
+    }
+
+    #[test]
+    fn answer_hash_matches_ion_pow_sdk() {
+        // Answer nonce encoded as by ion-pow-sdk: the hex encoding of a random hex string
+        // ("7e3a91"). The hash was computed independently with Argon2id (t=1, m=1000, p=1).
+        let hash = answer_hash(CHALLENGE_NONCE, "376533613931", REQUEST_BODY).unwrap();
+        assert_eq!(
+            hash,
+            "e31e234cc1c777850f358c9e547c012cb715a5463a6ccf0c141b1a7ac55e4b80"
+        );
+        assert_ne!(
+            answer_hash(CHALLENGE_NONCE, "376533613932", REQUEST_BODY).unwrap(),
+            hash
+        );
+    }
+
+    #[test]
+    fn answer_hash_rejects_invalid_hex() {
+        assert!(answer_hash("d1f", "376533613931", REQUEST_BODY).is_err());
+        assert!(answer_hash(CHALLENGE_NONCE, "zz", REQUEST_BODY).is_err());
+    }
+
+    #[test]
+    fn solve_meets_target() {
+        let target = format!("7{}", "f".repeat(63));
+        let answer = challenge(&target, u64::MAX)
+            .solve_blocking(REQUEST_BODY)
+            .unwrap();
+        assert!(answer.answer_hash <= target);
+        assert_eq!(
+            answer_hash(CHALLENGE_NONCE, &answer.answer_nonce, REQUEST_BODY).unwrap(),
+            answer.answer_hash
+        );
+        // The answer nonce is the hex encoding of a hex string.
+        let nonce = from_hex(&answer.answer_nonce).unwrap();
+        assert!(nonce.iter().all(u8::is_ascii_hexdigit));
+    }
+
+    #[test]
+    fn solve_fails_when_expired() {
+        let err = challenge(&"0".repeat(64), 0)
+            .solve_blocking(REQUEST_BODY)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("No proof-of-work answer"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn solve_rejects_invalid_target() {
+        assert!(challenge("ffff", 1).solve_blocking(REQUEST_BODY).is_err());
+        assert!(challenge(&"g".repeat(64), 1)
+            .solve_blocking(REQUEST_BODY)
+            .is_err());
+    }
+}
@@ -1,13 +1,24 @@
+use std::{collections::HashSet, convert::TryFrom};
+
 use anyhow::{ensure, Context, Result};
 use serde::Serialize;
+use ssi_dids::{error::Error as DIDError, VerificationRelationship};
+use ssi_jwk::JWK;
+use ssi_jws::Algorithm;
 
 use crate::{
     delta::{
-        patches::{DIDStatePatch, PublicKeyJwk},
+        patches::{
+            DIDStatePatch, DocumentState, PublicKey, PublicKeyEntry, PublicKeyJwk,
+            ServiceEndpointEntry,
+        },
         Delta,
     },
     error::SidetreeError,
-    operation::{CreateOperation, Operation},
+    operation::{
+        CreateOperation, DeactivateClaims, DeactivateOperation, Operation, RecoverOperation,
+        RecoveryClaims, UpdateClaims, UpdateOperation,
+    },
     suffix_data::SuffixData,
 };
 
@@ -28,6 +39,45 @@ pub trait Sidetree {
     /// Mentioned in [Sidetree §9. DID URI Composition](https://identity.foundation/sidetree/spec/v1.0.0/#did-uri-composition)
     const METHOD: &'static str;
 
+    /// [`SIGNATURE_ALGORITHM`](https://identity.foundation/sidetree/spec/v1.0.0/#sig-algorithm)
+    ///
+    /// JWS algorithm for signing the signed data of operations.
+    const SIGNATURE_ALGORITHM: Algorithm = Algorithm::ES256K;
+
+    /// [`MAX_OPERATION_HASH_LENGTH`](https://identity.foundation/sidetree/spec/v1.0.0/#max-operation-hash-length)
+    ///
+    /// Maximum length of encoded hashes in operations: delta hashes, commitments and reveal values.
+    const MAX_OPERATION_HASH_LENGTH: usize = 100;
+
+    /// [`MAX_DELTA_SIZE`](https://identity.foundation/sidetree/spec/v1.0.0/#max-delta-size)
+    ///
+    /// Maximum size in bytes of a canonicalized delta object. ION default: 1000.
+    const MAX_DELTA_SIZE: usize = 1000;
+
+    /// Maximum length of public key and service entry ids, which must be Base64url
+    const MAX_ID_LENGTH: usize = 50;
+
+    /// Maximum length of service entry types
+    const MAX_SERVICE_TYPE_LENGTH: usize = 30;
+
+    /// Whether public key entries may have a `controller` property. ION rejects it.
+    const ALLOW_CONTROLLER: bool = false;
+
+    /// Maximum length of the `controller` property of public key entries, if
+    /// [allowed](Self::ALLOW_CONTROLLER) and limited
+    const MAX_CONTROLLER_LENGTH: Option<usize> = None;
+
+    /// Whether public key entries may have a `publicKeyMultibase` property instead of
+    /// `publicKeyJwk`. ION rejects it.
+    const ALLOW_PKMB: bool = false;
+
+    /// Maximum length of the `publicKeyMultibase` property of public key entries, if
+    /// [allowed](Self::ALLOW_PKMB) and limited
+    const MAX_PKMB_LENGTH: Option<usize> = None;
+
+    /// Verification relationships allowed as `purposes` of public key entries
+    const ALLOWED_PURPOSES: &'static [VerificationRelationship] = &VerificationRelationship::ALL;
+
     /// [Public Key Commitment Scheme (Sidetree §6.2.1)][pkcs]
     ///
     /// [pkcs]: https://identity.foundation/sidetree/spec/v1.0.0/#public-key-commitment-scheme
@@ -41,6 +91,16 @@ pub trait Sidetree {
         Ok(commitment)
     }
 
+    /// [Reveal value (Sidetree §6.2.2)][rv] of a public key, for a commitment made with
+    /// [commitment_scheme](Self::commitment_scheme)
+    ///
+    /// [rv]: https://identity.foundation/sidetree/spec/v1.0.0/#reveal-value
+    fn reveal_value(pkjwk: &PublicKeyJwk) -> Result<String> {
+        let canonicalized_public_key =
+            Self::json_canonicalization_scheme(&pkjwk).context("Canonicalize JWK")?;
+        Ok(Self::hash(canonicalized_public_key.as_bytes()))
+    }
+
     /// [`JSON_CANONICALIZATION_SCHEME`](https://identity.foundation/sidetree/spec/v1.0.0/#json-canonicalization-scheme)
     fn json_canonicalization_scheme<T: Serialize>(value: &T) -> Result<String, SidetreeError> {
         serde_jcs::to_string(value).map_err(SidetreeError::JCS)
@@ -51,12 +111,6 @@ pub trait Sidetree {
     /// [Sidetree §6.1 Hashing Process](https://identity.foundation/sidetree/spec/#hashing-process)
     fn hash(data: &[u8]) -> String {
         let hash = Self::hash_protocol(data);
-        /*
-        ensure!(
-            hash.len() <= Self::MAX_OPERATION_HASH_LENGTH,
-            "Hash is too long"
-        );
-        */
         Self::data_encoding_scheme(&hash)
     }
 
@@ -170,7 +224,735 @@ pub trait Sidetree {
         };
 
         let create_operation = CreateOperation { delta, suffix_data };
+        Self::validate_create(&create_operation).context("Validate Create operation")?;
 
         Ok(Operation::Create(create_operation))
     }
+
+    /// Get the [DID Suffix](https://identity.foundation/sidetree/spec/v1.0.0/#did-suffix) of a
+    /// Sidetree-based DID, in short or long form.
+    fn did_suffix(did: &str) -> Result<String, DIDError> {
+        let invalid = || DIDError::InvalidDid(did.to_string());
+        let method_specific_id = did
+            .strip_prefix("did:")
+            .and_then(|did| did.strip_prefix(Self::METHOD))
+            .and_then(|did| did.strip_prefix(':'))
+            .ok_or_else(invalid)?;
+        let suffix = method_specific_id.split(':').next().unwrap_or_default();
+        if suffix.is_empty() || !is_base64url(suffix) {
+            return Err(invalid());
+        }
+        Ok(suffix.to_string())
+    }
+
+    /// [DID Suffix](https://identity.foundation/sidetree/spec/v1.0.0/#did-suffix) of a DID
+    /// created with the given Create Operation Suffix Data Object
+    ///
+    /// The DID Suffix is the [hash](Self::hash) of the canonicalized suffix data.
+    fn did_suffix_from_suffix_data(suffix_data: &SuffixData) -> Result<String> {
+        let suffix_data_string = Self::json_canonicalization_scheme(suffix_data)
+            .context("Canonicalize Create Operation Suffix Data Object")?;
+        Ok(Self::hash(suffix_data_string.as_bytes()))
+    }
+
+    /// Short-form DID for a [DID Suffix](https://identity.foundation/sidetree/spec/v1.0.0/#did-suffix)
+    ///
+    /// [Sidetree §9. DID URI Composition](https://identity.foundation/sidetree/spec/v1.0.0/#did-uri-composition)
+    fn did_from_suffix(did_suffix: &str) -> String {
+        format!("did:{}:{did_suffix}", Self::METHOD)
+    }
+
+    /// [Long-form DID][lfdu] for a Create operation
+    ///
+    /// The long-form DID is the short-form DID followed by the encoded, canonicalized initial
+    /// state (the suffix data and delta of the Create operation). It can be resolved before the
+    /// Create operation is anchored.
+    ///
+    /// [lfdu]: https://identity.foundation/sidetree/spec/v1.0.0/#long-form-did-uris
+    fn long_form_did(create_operation: &CreateOperation) -> Result<String> {
+        let did_suffix = Self::did_suffix_from_suffix_data(&create_operation.suffix_data)?;
+        let initial_state = Self::json_canonicalization_scheme(create_operation)
+            .context("Canonicalize long-form DID initial state")?;
+        let encoded_initial_state = Self::data_encoding_scheme(initial_state.as_bytes());
+        Ok(format!(
+            "{}:{encoded_initial_state}",
+            Self::did_from_suffix(&did_suffix)
+        ))
+    }
+
+    /// Parse a [long-form DID][lfdu] into its DID Suffix and the Create operation of its initial
+    /// state.
+    ///
+    /// The DID Suffix is re-derived from the initial state; a long-form DID whose suffix does not
+    /// match its initial state is rejected.
+    ///
+    /// [lfdu]: https://identity.foundation/sidetree/spec/v1.0.0/#long-form-did-uris
+    fn parse_long_form_did(did: &str) -> Result<(String, CreateOperation), DIDError> {
+        let invalid = |reason: &str| DIDError::InvalidDid(format!("{did}: {reason}"));
+        let did_suffix = Self::did_suffix(did)?;
+        let short_form_did = Self::did_from_suffix(&did_suffix);
+        let encoded_initial_state = did
+            .strip_prefix(&short_form_did)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .ok_or_else(|| invalid("not a long-form DID"))?;
+        let initial_state = base64::decode_config(encoded_initial_state, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| invalid("unable to decode initial state"))?;
+        let create_operation: CreateOperation = serde_json::from_slice(&initial_state)
+            .map_err(|_| invalid("unable to parse initial state"))?;
+        let computed_did_suffix = Self::did_suffix_from_suffix_data(&create_operation.suffix_data)
+            .map_err(|_| invalid("unable to compute DID suffix"))?;
+        if computed_did_suffix != did_suffix {
+            return Err(invalid("DID suffix does not match initial state"));
+        }
+        Ok((did_suffix, create_operation))
+    }
+
+    /// Sign the signed data object of an operation with [SIGNATURE_ALGORITHM][sa], producing a
+    /// compact JWS of its canonicalized JSON.
+    ///
+    /// [sa]: Self::SIGNATURE_ALGORITHM
+    fn sign<T: Serialize>(claims: &T, key: &JWK) -> Result<String> {
+        let claims_string =
+            Self::json_canonicalization_scheme(claims).context("Canonicalize claims")?;
+        let jws = ssi_jws::encode_sign(Self::SIGNATURE_ALGORITHM, &claims_string, key)
+            .context("Sign claims")?;
+        Ok(jws)
+    }
+
+    /// Update a Sidetree-based DID
+    ///
+    /// Construct an [Update operation][update] that applies `patches` to the DID state,
+    /// authorized by the current update key, and commits to `new_update_pk` for the next update.
+    ///
+    /// [update]: https://identity.foundation/sidetree/spec/v1.0.0/#update
+    fn update(
+        did_suffix: &str,
+        update_key: &JWK,
+        new_update_pk: &PublicKeyJwk,
+        patches: Vec<DIDStatePatch>,
+    ) -> Result<UpdateOperation> {
+        let update_pk =
+            PublicKeyJwk::try_from(update_key.to_public()).context("Convert update key")?;
+        ensure!(
+            &update_pk != new_update_pk,
+            "New update public key JWK payload must be different from the current one."
+        );
+
+        let update_commitment =
+            Self::commitment_scheme(new_update_pk).context("Generate update commitment")?;
+
+        let delta = Delta {
+            patches,
+            update_commitment,
+        };
+        let delta_string = Self::json_canonicalization_scheme(&delta)
+            .context("Canonicalize Update Operation Delta Object")?;
+        let delta_hash = Self::hash(delta_string.as_bytes());
+
+        let reveal_value = Self::reveal_value(&update_pk).context("Generate reveal value")?;
+        let claims = UpdateClaims {
+            update_key: update_pk,
+            delta_hash,
+        };
+        let signed_data = Self::sign(&claims, update_key).context("Sign Update operation")?;
+
+        let update_operation = UpdateOperation {
+            did_suffix: did_suffix.to_string(),
+            reveal_value,
+            delta,
+            signed_data,
+        };
+        Self::validate_update(&update_operation).context("Validate Update operation")?;
+        Ok(update_operation)
+    }
+
+    /// Recover a Sidetree-based DID
+    ///
+    /// Construct a [Recover operation][recover] that resets the DID state to the result of
+    /// applying `patches` to an empty document state, authorized by the current recovery key. The
+    /// operation commits to `new_update_pk` for the next update and `new_recovery_pk` for the
+    /// next recovery or deactivation.
+    ///
+    /// [recover]: https://identity.foundation/sidetree/spec/v1.0.0/#recover
+    fn recover_existing(
+        did_suffix: &str,
+        recovery_key: &JWK,
+        new_update_pk: &PublicKeyJwk,
+        new_recovery_pk: &PublicKeyJwk,
+        patches: Vec<DIDStatePatch>,
+        anchor_origin: Option<String>,
+    ) -> Result<RecoverOperation> {
+        let recovery_pk =
+            PublicKeyJwk::try_from(recovery_key.to_public()).context("Convert recovery key")?;
+        ensure!(
+            new_update_pk != new_recovery_pk,
+            "Update and recovery public key JWK payload must be different."
+        );
+        ensure!(
+            &recovery_pk != new_recovery_pk,
+            "New recovery public key JWK payload must be different from the current one."
+        );
+
+        let update_commitment =
+            Self::commitment_scheme(new_update_pk).context("Generate update commitment")?;
+
+        let delta = Delta {
+            patches,
+            update_commitment,
+        };
+        let delta_string = Self::json_canonicalization_scheme(&delta)
+            .context("Canonicalize Recover Operation Delta Object")?;
+        let delta_hash = Self::hash(delta_string.as_bytes());
+
+        let recovery_commitment =
+            Self::commitment_scheme(new_recovery_pk).context("Generate recovery commitment")?;
+
+        let reveal_value = Self::reveal_value(&recovery_pk).context("Generate reveal value")?;
+        let claims = RecoveryClaims {
+            recovery_key: recovery_pk,
+            recovery_commitment,
+            delta_hash,
+            anchor_origin,
+        };
+        let signed_data = Self::sign(&claims, recovery_key).context("Sign Recover operation")?;
+
+        let recover_operation = RecoverOperation {
+            did_suffix: did_suffix.to_string(),
+            reveal_value,
+            delta,
+            signed_data,
+        };
+        Self::validate_recover(&recover_operation).context("Validate Recover operation")?;
+        Ok(recover_operation)
+    }
+
+    /// Deactivate a Sidetree-based DID
+    ///
+    /// Construct a [Deactivate operation][deactivate], authorized by the current recovery key.
+    /// Deactivation is permanent: no further operations are accepted for the DID.
+    ///
+    /// [deactivate]: https://identity.foundation/sidetree/spec/v1.0.0/#deactivate
+    fn deactivate(did_suffix: &str, recovery_key: &JWK) -> Result<DeactivateOperation> {
+        let recovery_pk =
+            PublicKeyJwk::try_from(recovery_key.to_public()).context("Convert recovery key")?;
+        let reveal_value = Self::reveal_value(&recovery_pk).context("Generate reveal value")?;
+        let claims = DeactivateClaims {
+            did_suffix: did_suffix.to_string(),
+            recovery_key: recovery_pk,
+        };
+        let signed_data = Self::sign(&claims, recovery_key).context("Sign Deactivate operation")?;
+
+        let deactivate_operation = DeactivateOperation {
+            did_suffix: did_suffix.to_string(),
+            reveal_value,
+            signed_data,
+        };
+        Self::validate_deactivate(&deactivate_operation)
+            .context("Validate Deactivate operation")?;
+        Ok(deactivate_operation)
+    }
+
+    /// Validate an encoded hash, e.g. a commitment, against [MAX_OPERATION_HASH_LENGTH][mohl].
+    ///
+    /// [mohl]: Self::MAX_OPERATION_HASH_LENGTH
+    fn validate_hash(hash: &str) -> Result<()> {
+        ensure!(is_base64url(hash), "Hash must be Base64url: {hash}");
+        ensure!(
+            hash.len() <= Self::MAX_OPERATION_HASH_LENGTH,
+            "Hash exceeds maximum length of {}: {hash}",
+            Self::MAX_OPERATION_HASH_LENGTH
+        );
+        Ok(())
+    }
+
+    /// Validate the id of a public key or service entry against [MAX_ID_LENGTH][mil].
+    ///
+    /// [mil]: Self::MAX_ID_LENGTH
+    fn validate_id(id: &str) -> Result<()> {
+        ensure!(
+            !id.is_empty() && is_base64url(id),
+            "Id must be Base64url: {id}"
+        );
+        ensure!(
+            id.len() <= Self::MAX_ID_LENGTH,
+            "Id exceeds maximum length of {}: {id}",
+            Self::MAX_ID_LENGTH
+        );
+        Ok(())
+    }
+
+    /// Validate a public key entry against the protocol parameters.
+    fn validate_public_key_entry(entry: &PublicKeyEntry) -> Result<()> {
+        Self::validate_id(&entry.id)?;
+        if let Some(controller) = &entry.controller {
+            ensure!(
+                Self::ALLOW_CONTROLLER,
+                "Public key {} must not have a controller",
+                entry.id
+            );
+            if let Some(max_length) = Self::MAX_CONTROLLER_LENGTH {
+                ensure!(
+                    controller.len() <= max_length,
+                    "Controller of public key {} exceeds maximum length of {max_length}",
+                    entry.id
+                );
+            }
+        }
+        if let PublicKey::PublicKeyMultibase(pkmb) = &entry.public_key {
+            ensure!(
+                Self::ALLOW_PKMB,
+                "Public key {} must use publicKeyJwk, not publicKeyMultibase",
+                entry.id
+            );
+            if let Some(max_length) = Self::MAX_PKMB_LENGTH {
+                ensure!(
+                    pkmb.len() <= max_length,
+                    "publicKeyMultibase of public key {} exceeds maximum length of {max_length}",
+                    entry.id
+                );
+            }
+        }
+        let mut purposes = HashSet::new();
+        for purpose in &entry.purposes {
+            ensure!(
+                Self::ALLOWED_PURPOSES.contains(purpose),
+                "Purpose {} of public key {} is not allowed",
+                purpose.property_name(),
+                entry.id
+            );
+            ensure!(
+                purposes.insert(purpose),
+                "Duplicate purpose {} of public key {}",
+                purpose.property_name(),
+                entry.id
+            );
+        }
+        Ok(())
+    }
+
+    /// Validate a service entry against the protocol parameters.
+    fn validate_service_entry(entry: &ServiceEndpointEntry) -> Result<()> {
+        Self::validate_id(&entry.id)?;
+        ensure!(
+            entry.r#type.len() <= Self::MAX_SERVICE_TYPE_LENGTH,
+            "Type of service {} exceeds maximum length of {}",
+            entry.id,
+            Self::MAX_SERVICE_TYPE_LENGTH
+        );
+        Ok(())
+    }
+
+    /// Validate the document state of a `replace` patch against the protocol parameters.
+    fn validate_document_state(document: &DocumentState) -> Result<()> {
+        for entry in document.public_keys.iter().flatten() {
+            Self::validate_public_key_entry(entry)?;
+        }
+        for entry in document.services.iter().flatten() {
+            Self::validate_service_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Validate a DID state patch against the protocol parameters.
+    fn validate_patch(patch: &DIDStatePatch) -> Result<()> {
+        match patch {
+            DIDStatePatch::AddPublicKeys { public_keys } => {
+                for entry in public_keys {
+                    Self::validate_public_key_entry(entry)?;
+                }
+            }
+            DIDStatePatch::AddServices { services } => {
+                for entry in services {
+                    Self::validate_service_entry(entry)?;
+                }
+            }
+            DIDStatePatch::RemovePublicKeys { ids } | DIDStatePatch::RemoveServices { ids } => {
+                for id in ids {
+                    Self::validate_id(id)?;
+                }
+            }
+            DIDStatePatch::Replace { document } => Self::validate_document_state(document)?,
+            DIDStatePatch::IetfJsonPatch { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Validate a delta object against [MAX_DELTA_SIZE][mds] and the other protocol parameters.
+    ///
+    /// [mds]: Self::MAX_DELTA_SIZE
+    fn validate_delta(delta: &Delta) -> Result<()> {
+        let delta_string =
+            Self::json_canonicalization_scheme(delta).context("Canonicalize delta")?;
+        ensure!(
+            delta_string.len() <= Self::MAX_DELTA_SIZE,
+            "Delta size of {} bytes exceeds maximum of {}",
+            delta_string.len(),
+            Self::MAX_DELTA_SIZE
+        );
+        for patch in &delta.patches {
+            Self::validate_patch(patch)?;
+        }
+        Self::validate_hash(&delta.update_commitment).context("Update commitment")
+    }
+
+    /// Validate a Create operation against the protocol parameters.
+    fn validate_create(op: &CreateOperation) -> Result<()> {
+        Self::validate_delta(&op.delta)?;
+        Self::validate_hash(&op.suffix_data.delta_hash).context("Delta hash")?;
+        Self::validate_hash(&op.suffix_data.recovery_commitment).context("Recovery commitment")
+    }
+
+    /// Validate an Update operation against the protocol parameters.
+    fn validate_update(op: &UpdateOperation) -> Result<()> {
+        Self::validate_hash(&op.did_suffix).context("DID Suffix")?;
+        Self::validate_hash(&op.reveal_value).context("Reveal value")?;
+        Self::validate_delta(&op.delta)
+    }
+
+    /// Validate a Recover operation against the protocol parameters.
+    fn validate_recover(op: &RecoverOperation) -> Result<()> {
+        Self::validate_hash(&op.did_suffix).context("DID Suffix")?;
+        Self::validate_hash(&op.reveal_value).context("Reveal value")?;
+        Self::validate_delta(&op.delta)
+    }
+
+    /// Validate a Deactivate operation against the protocol parameters.
+    fn validate_deactivate(op: &DeactivateOperation) -> Result<()> {
+        Self::validate_hash(&op.did_suffix).context("DID Suffix")?;
+        Self::validate_hash(&op.reveal_value).context("Reveal value")
+    }
+
+    /// Validate an operation against the protocol parameters, before submitting it to a
+    /// Sidetree node.
+    fn validate_operation(op: &Operation) -> Result<()> {
+        match op {
+            Operation::Create(op) => Self::validate_create(op),
+            Operation::Update(op) => Self::validate_update(op),
+            Operation::Recover(op) => Self::validate_recover(op),
+            Operation::Deactivate(op) => Self::validate_deactivate(op),
+        }
+    }
+}
+
+/// Whether a string uses only the Base64url alphabet, without padding
+fn is_base64url(string: &str) -> bool {
+    string
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{delta::patches::DocumentState, ION};
+    use serde_json::json;
+
+    fn public_key_jwk(key: &JWK) -> PublicKeyJwk {
+        PublicKeyJwk::try_from(key.to_public()).unwrap()
+    }
+
+    /// Verify the signed data of an operation and decode its claims.
+    fn verify_signed_data<T: serde::de::DeserializeOwned>(signed_data: &str, key: &JWK) -> T {
+        let (header, payload) = ssi_jws::decode_verify(signed_data, key).unwrap();
+        assert_eq!(header.algorithm, ION::SIGNATURE_ALGORITHM);
+        serde_json::from_slice(&payload).unwrap()
+    }
+
+    fn delta_hash(delta: &Delta) -> String {
+        let delta_string = ION::json_canonicalization_scheme(delta).unwrap();
+        ION::hash(delta_string.as_bytes())
+    }
+
+    #[test]
+    fn update_operation() {
+        let update_key = JWK::generate_secp256k1().unwrap();
+        let update_pk = public_key_jwk(&update_key);
+        let new_update_pk = public_key_jwk(&JWK::generate_secp256k1().unwrap());
+        let patches = vec![DIDStatePatch::RemoveServices {
+            ids: vec!["s1".to_string()],
+        }];
+
+        let op = ION::update("suffix", &update_key, &new_update_pk, patches).unwrap();
+        assert_eq!(op.did_suffix, "suffix");
+        assert_eq!(op.reveal_value, ION::reveal_value(&update_pk).unwrap());
+        assert!(matches!(
+            &op.delta.patches[..],
+            [DIDStatePatch::RemoveServices { ids }] if ids == &["s1"]
+        ));
+        assert_eq!(
+            op.delta.update_commitment,
+            ION::commitment_scheme(&new_update_pk).unwrap()
+        );
+        let claims: UpdateClaims = verify_signed_data(&op.signed_data, &update_key);
+        assert_eq!(claims.update_key, update_pk);
+        assert_eq!(claims.delta_hash, delta_hash(&op.delta));
+
+        // The next update key must differ from the current one.
+        assert!(ION::update("suffix", &update_key, &update_pk, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn recover_operation() {
+        let recovery_key = JWK::generate_secp256k1().unwrap();
+        let recovery_pk = public_key_jwk(&recovery_key);
+        let new_update_pk = public_key_jwk(&JWK::generate_secp256k1().unwrap());
+        let new_recovery_pk = public_key_jwk(&JWK::generate_secp256k1().unwrap());
+        let patches = vec![DIDStatePatch::Replace {
+            document: DocumentState::default(),
+        }];
+
+        let op = ION::recover_existing(
+            "suffix",
+            &recovery_key,
+            &new_update_pk,
+            &new_recovery_pk,
+            patches,
+            Some("origin".to_string()),
+        )
+        .unwrap();
+        assert_eq!(op.did_suffix, "suffix");
+        assert_eq!(op.reveal_value, ION::reveal_value(&recovery_pk).unwrap());
+        assert!(matches!(
+            &op.delta.patches[..],
+            [DIDStatePatch::Replace { .. }]
+        ));
+        assert_eq!(
+            op.delta.update_commitment,
+            ION::commitment_scheme(&new_update_pk).unwrap()
+        );
+        let claims: RecoveryClaims = verify_signed_data(&op.signed_data, &recovery_key);
+        assert_eq!(claims.recovery_key, recovery_pk);
+        assert_eq!(
+            claims.recovery_commitment,
+            ION::commitment_scheme(&new_recovery_pk).unwrap()
+        );
+        assert_eq!(claims.delta_hash, delta_hash(&op.delta));
+        assert_eq!(claims.anchor_origin.as_deref(), Some("origin"));
+
+        // The new keys must differ from each other and from the current recovery key.
+        assert!(ION::recover_existing(
+            "suffix",
+            &recovery_key,
+            &new_update_pk,
+            &new_update_pk,
+            Vec::new(),
+            None
+        )
+        .is_err());
+        assert!(ION::recover_existing(
+            "suffix",
+            &recovery_key,
+            &new_update_pk,
+            &recovery_pk,
+            Vec::new(),
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn deactivate_operation() {
+        let recovery_key = JWK::generate_secp256k1().unwrap();
+        let recovery_pk = public_key_jwk(&recovery_key);
+
+        let op = ION::deactivate("suffix", &recovery_key).unwrap();
+        assert_eq!(op.did_suffix, "suffix");
+        assert_eq!(op.reveal_value, ION::reveal_value(&recovery_pk).unwrap());
+        let claims: DeactivateClaims = verify_signed_data(&op.signed_data, &recovery_key);
+        assert_eq!(claims.did_suffix, "suffix");
+        assert_eq!(claims.recovery_key, recovery_pk);
+
+        let other_key = JWK::generate_secp256k1().unwrap();
+        assert!(ssi_jws::decode_verify(&op.signed_data, &other_key).is_err());
+    }
+
+    /// Sidetree implementation with optional properties allowed, but limited
+    struct Limited;
+
+    impl Sidetree for Limited {
+        const METHOD: &'static str = "limited";
+        const ALLOW_CONTROLLER: bool = true;
+        const MAX_CONTROLLER_LENGTH: Option<usize> = Some(20);
+        const ALLOW_PKMB: bool = true;
+        const MAX_PKMB_LENGTH: Option<usize> = Some(10);
+        const ALLOWED_PURPOSES: &'static [VerificationRelationship] =
+            &[VerificationRelationship::Authentication];
+    }
+
+    fn jwk_entry(id: &str, purposes: &[&str]) -> PublicKeyEntry {
+        serde_json::from_value(json!({
+            "id": id,
+            "type": "JsonWebSignature2020",
+            "publicKeyJwk": {"kty": "EC", "crv": "secp256k1", "x": "x", "y": "y"},
+            "purposes": purposes,
+        }))
+        .unwrap()
+    }
+
+    fn multibase_entry(multibase: &str) -> PublicKeyEntry {
+        serde_json::from_value(json!({
+            "id": "key-1",
+            "type": "Ed25519VerificationKey2020",
+            "publicKeyMultibase": multibase,
+        }))
+        .unwrap()
+    }
+
+    fn service_entry(id: &str, type_: &str) -> ServiceEndpointEntry {
+        serde_json::from_value(json!({
+            "id": id,
+            "type": type_,
+            "serviceEndpoint": "https://example.com",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn validate_id() {
+        ION::validate_id("key-1_A").unwrap();
+        ION::validate_id(&"a".repeat(ION::MAX_ID_LENGTH)).unwrap();
+        assert!(ION::validate_id(&"a".repeat(ION::MAX_ID_LENGTH + 1)).is_err());
+        assert!(ION::validate_id("").is_err());
+        assert!(ION::validate_id("#key-1").is_err());
+        assert!(ION::validate_id("key 1").is_err());
+    }
+
+    #[test]
+    fn validate_hash() {
+        let hash = ION::hash(b"data");
+        ION::validate_hash(&hash).unwrap();
+        assert!(ION::validate_hash(&"E".repeat(ION::MAX_OPERATION_HASH_LENGTH + 1)).is_err());
+        assert!(ION::validate_hash("EiA+/=").is_err());
+    }
+
+    #[test]
+    fn validate_public_key_entry_purposes() {
+        ION::validate_public_key_entry(&jwk_entry("key-1", &[])).unwrap();
+        ION::validate_public_key_entry(&jwk_entry(
+            "key-1",
+            &[
+                "authentication",
+                "assertionMethod",
+                "keyAgreement",
+                "capabilityInvocation",
+                "capabilityDelegation",
+            ],
+        ))
+        .unwrap();
+        assert!(ION::validate_public_key_entry(&jwk_entry(
+            "key-1",
+            &["authentication", "authentication"]
+        ))
+        .is_err());
+        assert!(ION::validate_public_key_entry(&jwk_entry("key#1", &[])).is_err());
+        Limited::validate_public_key_entry(&jwk_entry("key-1", &["authentication"])).unwrap();
+        assert!(
+            Limited::validate_public_key_entry(&jwk_entry("key-1", &["assertionMethod"])).is_err()
+        );
+    }
+
+    #[test]
+    fn validate_public_key_entry_controller() {
+        let mut entry = jwk_entry("key-1", &[]);
+        entry.controller = Some("did:example:a".to_string());
+        assert!(ION::validate_public_key_entry(&entry).is_err());
+        Limited::validate_public_key_entry(&entry).unwrap();
+        entry.controller = Some("did:example:".to_string() + &"a".repeat(20));
+        assert!(Limited::validate_public_key_entry(&entry).is_err());
+    }
+
+    #[test]
+    fn validate_public_key_entry_multibase() {
+        assert!(ION::validate_public_key_entry(&multibase_entry("z6Mk")).is_err());
+        Limited::validate_public_key_entry(&multibase_entry("z6Mk")).unwrap();
+        assert!(Limited::validate_public_key_entry(&multibase_entry("z6MkTooLong")).is_err());
+    }
+
+    #[test]
+    fn validate_service_entry() {
+        ION::validate_service_entry(&service_entry("domain", "LinkedDomains")).unwrap();
+        let max_type = "T".repeat(ION::MAX_SERVICE_TYPE_LENGTH);
+        ION::validate_service_entry(&service_entry("domain", &max_type)).unwrap();
+        assert!(
+            ION::validate_service_entry(&service_entry("domain", &format!("{}T", max_type)))
+                .is_err()
+        );
+        assert!(ION::validate_service_entry(&service_entry("#domain", "LinkedDomains")).is_err());
+    }
+
+    #[test]
+    fn validate_patch() {
+        let patch = |value| serde_json::from_value::<DIDStatePatch>(value).unwrap();
+        ION::validate_patch(&patch(json!({
+            "action": "remove-services",
+            "ids": ["domain"],
+        })))
+        .unwrap();
+        assert!(ION::validate_patch(&patch(json!({
+            "action": "remove-public-keys",
+            "ids": ["#key-1"],
+        })))
+        .is_err());
+        assert!(ION::validate_patch(&patch(json!({
+            "action": "replace",
+            "document": {
+                "services": [{"id": "domain", "type": "T".repeat(31), "serviceEndpoint": "https://example.com"}],
+            },
+        })))
+        .is_err());
+    }
+
+    #[test]
+    fn validate_delta_size() {
+        let update_commitment = ION::hash(b"commitment");
+        let delta = |count: usize| Delta {
+            patches: vec![DIDStatePatch::AddPublicKeys {
+                public_keys: (0..count)
+                    .map(|i| jwk_entry(&format!("key-{}", i), &["authentication"]))
+                    .collect(),
+            }],
+            update_commitment: update_commitment.clone(),
+        };
+        ION::validate_delta(&delta(1)).unwrap();
+        let err = ION::validate_delta(&delta(20)).unwrap_err();
+        assert!(err.to_string().contains("exceeds maximum"), "{}", err);
+    }
+
+    #[test]
+    fn operations_are_validated() {
+        let update_pk = public_key_jwk(&JWK::generate_secp256k1().unwrap());
+        let recovery_pk = public_key_jwk(&JWK::generate_secp256k1().unwrap());
+        let create = ION::create_existing(&update_pk, &recovery_pk, vec![]).unwrap();
+        ION::validate_operation(&create).unwrap();
+        assert!(ION::create_existing(
+            &update_pk,
+            &recovery_pk,
+            vec![DIDStatePatch::AddPublicKeys {
+                public_keys: vec![jwk_entry(&"a".repeat(51), &[])],
+            }],
+        )
+        .is_err());
+
+        let did_suffix = create.did_suffix::<ION>().unwrap();
+        let update_key = JWK::generate_secp256k1().unwrap();
+        let update = ION::update(&did_suffix, &update_key, &update_pk, vec![]).unwrap();
+        ION::validate_operation(&Operation::Update(update)).unwrap();
+        assert!(ION::update("not a suffix", &update_key, &update_pk, vec![]).is_err());
+
+        let recovery_key = JWK::generate_secp256k1().unwrap();
+        let recover = ION::recover_existing(
+            &did_suffix,
+            &recovery_key,
+            &update_pk,
+            &recovery_pk,
+            vec![],
+            None,
+        )
+        .unwrap();
+        ION::validate_operation(&Operation::Recover(recover)).unwrap();
+
+        let deactivate = ION::deactivate(&did_suffix, &recovery_key).unwrap();
+        ION::validate_operation(&Operation::Deactivate(deactivate)).unwrap();
+        assert!(ION::deactivate("not a suffix", &recovery_key).is_err());
+    }
 }
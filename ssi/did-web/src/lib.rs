@@ -13,6 +13,7 @@ impl DIDMethod for DIDWEB {
     fn create(&self, _create: DIDCreate) -> Result<DIDMethodTransaction, DIDMethodError> {
         Ok(DIDMethodTransaction {
             did_method: "did:web".to_string(),
+            did: None,
             value: Value::Null,
         })
     }
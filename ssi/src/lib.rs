@@ -1,2 +1,3 @@
 pub use ssi_dids;
 pub use ssi_jwk;
+pub use ssi_jws;
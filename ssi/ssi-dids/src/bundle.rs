@@ -0,0 +1,484 @@
+//! Static DID resolution from local bundles
+//!
+//! A bundle is a directory of DID resolution results, one JSON file per DID, listed in a
+//! [manifest](BundleManifest). Bundles let DIDs be resolved without network access, e.g. in
+//! tests or by air-gapped verifiers. The manifest may be signed, so that a bundle can be traced
+//! to the party that exported it.
+
+use std::{collections::HashMap, fs, io, path::Path};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ssi_jwk::JWK;
+use thiserror::Error;
+
+use crate::{
+    cache::json_filename,
+    did_resolve::{
+        DIDResolver, DocumentMetadata, ResolutionInputMetadata, ResolutionMetadata,
+        ResolutionResult,
+    },
+    error::ErrorCode,
+    Document,
+};
+
+/// Filename of the [bundle manifest](BundleManifest)
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Filename of the bundle manifest signature, a JWS in compact serialization with the manifest
+/// as payload
+pub const MANIFEST_SIGNATURE_FILE: &str = "manifest.jws";
+
+/// Error reading or writing a bundle
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("Unable to access bundle file {0}")]
+    IO(String, #[source] io::Error),
+    #[error("Unable to parse bundle file {0}")]
+    Json(String, #[source] serde_json::Error),
+    /// The digest of a file does not match the manifest
+    #[error("Digest mismatch for bundle file {0}")]
+    DigestMismatch(String),
+    /// The manifest signature is invalid, or does not sign the manifest
+    #[error("Invalid bundle manifest signature")]
+    Signature(#[from] ssi_jws::Error),
+    #[error("Bundle manifest does not match signed manifest")]
+    ManifestMismatch,
+    #[error("Invalid bundle filename {0}")]
+    InvalidFilename(String),
+}
+
+/// Resolution results for a DID in a bundle
+///
+/// Serialized as the [resolution result](ResolutionResult) for the current version of the DID
+/// document, with previous versions in a `versions` property. Versions are selected by the
+/// `versionId` and `updated` (or `created`) properties of their document metadata.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleEntry {
+    /// Resolution result for the current version
+    #[serde(flatten)]
+    pub result: ResolutionResult,
+
+    /// Resolution results for other versions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<ResolutionResult>,
+}
+
+impl BundleEntry {
+    /// Get the resolution result for the version requested by the `versionId` and
+    /// `versionTime` input metadata properties, or the current version if none is requested.
+    ///
+    /// For `versionTime`, the latest version that became current at or before that time is
+    /// selected.
+    pub fn get_version(
+        &self,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> Option<&ResolutionResult> {
+        if !input_metadata.is_versioned() {
+            return Some(&self.result);
+        }
+        std::iter::once(&self.result)
+            .chain(&self.versions)
+            .filter_map(|result| {
+                let doc_meta = result.did_document_metadata.as_ref()?;
+                match doc_meta.matches_version(input_metadata) {
+                    true => Some((doc_meta.version_start(), result)),
+                    false => None,
+                }
+            })
+            .max_by_key(|(start, _)| *start)
+            .map(|(_, result)| result)
+    }
+}
+
+/// Bundle manifest, listing the file for each DID in the bundle
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    /// Time the bundle was written
+    pub created: DateTime<Utc>,
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// Manifest entry for a DID
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub did: String,
+    /// Filename of the [BundleEntry], relative to the bundle directory
+    pub file: String,
+    /// Base64url-encoded SHA-256 digest of the file
+    pub digest: String,
+}
+
+fn digest(data: &[u8]) -> String {
+    base64::encode_config(Sha256::digest(data), base64::URL_SAFE_NO_PAD)
+}
+
+fn read_file(dir: &Path, file: &str) -> Result<Vec<u8>, BundleError> {
+    fs::read(dir.join(file)).map_err(|e| BundleError::IO(file.to_string(), e))
+}
+
+fn write_file(dir: &Path, file: &str, data: &[u8]) -> Result<(), BundleError> {
+    fs::write(dir.join(file), data).map_err(|e| BundleError::IO(file.to_string(), e))
+}
+
+/// A [DIDResolver] serving resolution results from memory, loaded from a bundle directory or
+/// inserted directly.
+#[derive(Debug, Clone, Default)]
+pub struct StaticDIDResolver {
+    pub entries: HashMap<String, BundleEntry>,
+}
+
+impl StaticDIDResolver {
+    /// Construct an empty resolver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the resolution results for a DID.
+    pub fn insert(&mut self, did: &str, entry: BundleEntry) -> Option<BundleEntry> {
+        self.entries.insert(did.to_string(), entry)
+    }
+
+    /// Load a bundle directory.
+    ///
+    /// File digests are checked against the manifest. The manifest signature, if any, is not
+    /// checked; use [from_dir_verified](Self::from_dir_verified) for that.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, BundleError> {
+        let dir = dir.as_ref();
+        let manifest_bytes = read_file(dir, MANIFEST_FILE)?;
+        Self::from_manifest(dir, &manifest_bytes)
+    }
+
+    /// Load a bundle directory, requiring a manifest signature by the given key.
+    pub fn from_dir_verified(dir: impl AsRef<Path>, key: &JWK) -> Result<Self, BundleError> {
+        let dir = dir.as_ref();
+        let manifest_bytes = read_file(dir, MANIFEST_FILE)?;
+        let jws = read_file(dir, MANIFEST_SIGNATURE_FILE)?;
+        let jws = String::from_utf8_lossy(&jws);
+        let (_header, payload) = ssi_jws::decode_verify(jws.trim(), key)?;
+        if payload != manifest_bytes {
+            return Err(BundleError::ManifestMismatch);
+        }
+        Self::from_manifest(dir, &manifest_bytes)
+    }
+
+    fn from_manifest(dir: &Path, manifest_bytes: &[u8]) -> Result<Self, BundleError> {
+        let manifest: BundleManifest = serde_json::from_slice(manifest_bytes)
+            .map_err(|e| BundleError::Json(MANIFEST_FILE.to_string(), e))?;
+        let mut resolver = Self::new();
+        for ManifestEntry {
+            did,
+            file,
+            digest: expected_digest,
+        } in manifest.entries
+        {
+            // Entries must be plain filenames within the bundle directory.
+            if file.contains(['/', '\\']) || file.starts_with('.') {
+                return Err(BundleError::InvalidFilename(file));
+            }
+            let bytes = read_file(dir, &file)?;
+            if digest(&bytes) != expected_digest {
+                return Err(BundleError::DigestMismatch(file));
+            }
+            let entry: BundleEntry =
+                serde_json::from_slice(&bytes).map_err(|e| BundleError::Json(file, e))?;
+            resolver.insert(&did, entry);
+        }
+        Ok(resolver)
+    }
+
+    /// Write the resolution results to a bundle directory, creating it if needed.
+    ///
+    /// If a signing key is given, the manifest is signed with it. `key_id` is set as the `kid`
+    /// of the signature, e.g. to identify the signer's verification method.
+    pub fn write_dir(
+        &self,
+        dir: impl AsRef<Path>,
+        signing_key: Option<&JWK>,
+        key_id: Option<String>,
+    ) -> Result<BundleManifest, BundleError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).map_err(|e| BundleError::IO(dir.display().to_string(), e))?;
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|(did, _)| *did);
+        let mut manifest_entries = Vec::with_capacity(entries.len());
+        for (did, entry) in entries {
+            let file = json_filename(did);
+            let bytes =
+                serde_json::to_vec_pretty(entry).map_err(|e| BundleError::Json(file.clone(), e))?;
+            write_file(dir, &file, &bytes)?;
+            manifest_entries.push(ManifestEntry {
+                did: did.clone(),
+                digest: digest(&bytes),
+                file,
+            });
+        }
+        let manifest = BundleManifest {
+            created: Utc::now(),
+            entries: manifest_entries,
+        };
+        let manifest_bytes = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| BundleError::Json(MANIFEST_FILE.to_string(), e))?;
+        write_file(dir, MANIFEST_FILE, &manifest_bytes)?;
+        if let Some(key) = signing_key {
+            let mut header = ssi_jws::Header::new(ssi_jws::algorithm_for_jwk(key)?);
+            header.key_id = key_id;
+            let payload = String::from_utf8_lossy(&manifest_bytes);
+            let jws = ssi_jws::encode_sign_custom_header(&payload, key, &header)?;
+            write_file(dir, MANIFEST_SIGNATURE_FILE, jws.as_bytes())?;
+        }
+        Ok(manifest)
+    }
+}
+
+#[async_trait]
+impl DIDResolver for StaticDIDResolver {
+    async fn resolve(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (
+        ResolutionMetadata,
+        Option<Document>,
+        Option<DocumentMetadata>,
+    ) {
+        let result = self
+            .entries
+            .get(did)
+            .and_then(|entry| entry.get_version(input_metadata));
+        match result {
+            Some(result) => result.clone().into_parts(),
+            None => (
+                ResolutionMetadata::from_error(ErrorCode::NotFound),
+                None,
+                None,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    const DID: &str = "did:example:123";
+
+    fn temp_bundle_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ssi-dids-bundle-test-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn versioned_result(did: &str, version: &str) -> ResolutionResult {
+        let year = 2020 + version.parse::<i32>().unwrap();
+        let doc_meta = DocumentMetadata {
+            updated: Some(Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap()),
+            version_id: Some(version.to_string()),
+            ..Default::default()
+        };
+        let mut doc = Document::new(did);
+        doc.also_known_as = Some(vec![format!("https://example.com/v{version}")]);
+        ResolutionResult::new(ResolutionMetadata::default(), Some(doc), Some(doc_meta))
+    }
+
+    fn resolver() -> StaticDIDResolver {
+        let mut resolver = StaticDIDResolver::new();
+        resolver.insert(
+            DID,
+            BundleEntry {
+                result: versioned_result(DID, "2"),
+                versions: vec![versioned_result(DID, "1")],
+            },
+        );
+        resolver.insert(
+            "did:example:456",
+            BundleEntry {
+                result: versioned_result("did:example:456", "1"),
+                versions: Vec::new(),
+            },
+        );
+        resolver
+    }
+
+    fn version(version: &str) -> ResolutionInputMetadata {
+        ResolutionInputMetadata {
+            version_id: Some(version.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn version_time(year: i32, month: u32) -> ResolutionInputMetadata {
+        ResolutionInputMetadata {
+            version_time: Some(Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    async fn resolve_aka(
+        resolver: &StaticDIDResolver,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> Option<String> {
+        let (res_meta, doc_opt, _) = resolver.resolve(DID, input_metadata).await;
+        if res_meta.error.is_some() {
+            assert_eq!(res_meta.error, Some(ErrorCode::NotFound));
+            return None;
+        }
+        Some(doc_opt.unwrap().also_known_as.unwrap().remove(0))
+    }
+
+    #[test]
+    fn get_version() {
+        let entry = resolver().entries.remove(DID).unwrap();
+        let aka =
+            |result: &ResolutionResult| result.did_document.as_ref().unwrap().also_known_as.clone();
+        let v1 = Some(vec!["https://example.com/v1".to_string()]);
+        let v2 = Some(vec!["https://example.com/v2".to_string()]);
+        assert_eq!(aka(entry.get_version(&Default::default()).unwrap()), v2);
+        assert_eq!(aka(entry.get_version(&version("2")).unwrap()), v2);
+        assert_eq!(aka(entry.get_version(&version("1")).unwrap()), v1);
+        assert!(entry.get_version(&version("3")).is_none());
+        assert!(BundleEntry::default().get_version(&version("1")).is_none());
+
+        // versionTime selects the latest version current at that time.
+        assert_eq!(aka(entry.get_version(&version_time(2021, 6)).unwrap()), v1);
+        assert_eq!(aka(entry.get_version(&version_time(2022, 1)).unwrap()), v2);
+        assert_eq!(aka(entry.get_version(&version_time(2030, 1)).unwrap()), v2);
+        assert!(entry.get_version(&version_time(2020, 6)).is_none());
+        // Both must match when given.
+        let mut both = version_time(2022, 6);
+        both.version_id = Some("1".to_string());
+        assert_eq!(aka(entry.get_version(&both).unwrap()), v1);
+        both.version_time = version_time(2020, 6).version_time;
+        assert!(entry.get_version(&both).is_none());
+    }
+
+    #[tokio::test]
+    async fn resolve_versions() {
+        let resolver = resolver();
+        assert_eq!(
+            resolve_aka(&resolver, &Default::default()).await.as_deref(),
+            Some("https://example.com/v2")
+        );
+        assert_eq!(
+            resolve_aka(&resolver, &version("1")).await.as_deref(),
+            Some("https://example.com/v1")
+        );
+        assert_eq!(
+            resolve_aka(&resolver, &version_time(2021, 6))
+                .await
+                .as_deref(),
+            Some("https://example.com/v1")
+        );
+        assert_eq!(resolve_aka(&resolver, &version("3")).await, None);
+        let (res_meta, _, _) = resolver
+            .resolve("did:example:789", &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(res_meta.error, Some(ErrorCode::NotFound));
+    }
+
+    #[tokio::test]
+    async fn write_and_load_signed_bundle() {
+        let dir = temp_bundle_dir("signed");
+        let key = JWK::generate_secp256k1().unwrap();
+        let manifest = resolver()
+            .write_dir(&dir, Some(&key), Some(format!("{DID}#key-1")))
+            .unwrap();
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.entries[0].did, DID);
+        assert_eq!(manifest.entries[0].file, json_filename(DID));
+
+        let loaded = StaticDIDResolver::from_dir_verified(&dir, &key.to_public()).unwrap();
+        assert_eq!(loaded.entries.len(), 2);
+        assert_eq!(
+            resolve_aka(&loaded, &version("1")).await.as_deref(),
+            Some("https://example.com/v1")
+        );
+        assert!(StaticDIDResolver::from_dir(&dir).is_ok());
+
+        // Another key does not verify the manifest.
+        let other_key = JWK::generate_secp256k1().unwrap();
+        assert!(matches!(
+            StaticDIDResolver::from_dir_verified(&dir, &other_key),
+            Err(BundleError::Signature(_))
+        ));
+
+        // A manifest edited after signing is rejected.
+        let mut manifest_bytes = fs::read(dir.join(MANIFEST_FILE)).unwrap();
+        manifest_bytes.push(b'\n');
+        fs::write(dir.join(MANIFEST_FILE), manifest_bytes).unwrap();
+        assert!(matches!(
+            StaticDIDResolver::from_dir_verified(&dir, &key),
+            Err(BundleError::ManifestMismatch)
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unsigned_bundle() {
+        let dir = temp_bundle_dir("unsigned");
+        resolver().write_dir(&dir, None, None).unwrap();
+        assert!(!dir.join(MANIFEST_SIGNATURE_FILE).exists());
+        assert_eq!(StaticDIDResolver::from_dir(&dir).unwrap().entries.len(), 2);
+        let key = JWK::generate_secp256k1().unwrap();
+        assert!(matches!(
+            StaticDIDResolver::from_dir_verified(&dir, &key),
+            Err(BundleError::IO(file, _)) if file == MANIFEST_SIGNATURE_FILE
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn digest_mismatch() {
+        let dir = temp_bundle_dir("digest");
+        resolver().write_dir(&dir, None, None).unwrap();
+        let file = json_filename(DID);
+        let mut entry: BundleEntry =
+            serde_json::from_slice(&fs::read(dir.join(&file)).unwrap()).unwrap();
+        entry.versions.clear();
+        fs::write(dir.join(&file), serde_json::to_vec(&entry).unwrap()).unwrap();
+        assert!(matches!(
+            StaticDIDResolver::from_dir(&dir),
+            Err(BundleError::DigestMismatch(mismatched)) if mismatched == file
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_filenames() {
+        let dir = temp_bundle_dir("filenames");
+        let manifest = resolver().write_dir(&dir, None, None).unwrap();
+        for file in [
+            "../did.json",
+            "sub/did.json",
+            "sub\\did.json",
+            ".hidden.json",
+        ] {
+            let mut manifest = manifest.clone();
+            manifest.entries[0].file = file.to_string();
+            fs::write(
+                dir.join(MANIFEST_FILE),
+                serde_json::to_vec(&manifest).unwrap(),
+            )
+            .unwrap();
+            assert!(
+                matches!(
+                    StaticDIDResolver::from_dir(&dir),
+                    Err(BundleError::InvalidFilename(invalid)) if invalid == file
+                ),
+                "{}",
+                file
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,534 @@
+//! Caching layer for DID resolvers
+//!
+//! Per [DID Resolution §Caching](https://w3c-ccg.github.io/did-resolution/#caching), resolution
+//! results may be cached unless the caller passes `noCache` in the resolution input metadata.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    did_resolve::{
+        DIDResolver, DocumentMetadata, ResolutionInputMetadata, ResolutionMetadata,
+        ResolutionResult,
+    },
+    Document,
+};
+
+/// Default maximum number of in-memory cache entries
+pub const DEFAULT_MAX_ENTRIES: usize = 1000;
+
+/// Default time-to-live for cache entries
+pub const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+/// A cached resolution result with the time it was stored.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntry {
+    /// Time the result was stored
+    pub stored_at: SystemTime,
+    /// Cached resolution result
+    pub result: ResolutionResult,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        match self.stored_at.elapsed() {
+            Ok(age) => age < ttl,
+            // Stored in the future: treat as expired rather than trusting the clock.
+            Err(_) => false,
+        }
+    }
+}
+
+/// Persistent storage backend for [CachingDIDResolver]
+pub trait CacheStore: Send + Sync {
+    /// Look up a cache entry by key.
+    fn get(&self, key: &str) -> Option<CacheEntry>;
+
+    /// Store a cache entry under a key.
+    fn put(&self, key: &str, entry: &CacheEntry);
+
+    /// Remove a cache entry.
+    fn remove(&self, key: &str);
+}
+
+/// On-disk [CacheStore], storing one JSON file per cache key in a directory
+///
+/// This allows separate processes, such as successive CLI invocations, to share cached
+/// resolution results.
+pub struct FileCacheStore {
+    /// Directory containing the cache files
+    pub dir: PathBuf,
+}
+
+impl FileCacheStore {
+    /// Construct a file cache store in the given directory. The directory is created on first
+    /// write if it does not exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(json_filename(key))
+    }
+}
+
+/// Get a portable JSON filename for a key such as a DID, escaping everything except
+/// alphanumerics, '-' and '.'.
+pub(crate) fn json_filename(key: &str) -> String {
+    let mut filename = String::with_capacity(key.len() + 5);
+    for byte in key.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'.' {
+            filename.push(byte as char);
+        } else {
+            filename.push_str(&format!("_{byte:02x}"));
+        }
+    }
+    filename.push_str(".json");
+    filename
+}
+
+impl CacheStore for FileCacheStore {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let bytes = fs::read(self.path(key)).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    fn put(&self, key: &str, entry: &CacheEntry) {
+        // Caching is best-effort: failure to write must not fail resolution.
+        if fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+        if let Ok(bytes) = serde_json::to_vec(entry) {
+            let _ = fs::write(self.path(key), bytes);
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key));
+    }
+}
+
+/// A [DIDResolver] that caches results of another resolver in memory, and optionally in a
+/// [CacheStore].
+///
+/// Only successful resolutions of documents that are not deactivated are cached. The cache is
+/// bypassed when the input metadata has `noCache` set to `true`. Requests with additional input
+/// metadata properties, which may change the result, are neither looked up nor cached.
+pub struct CachingDIDResolver<R: DIDResolver> {
+    /// Underlying resolver
+    pub resolver: R,
+    /// Maximum number of entries held in memory
+    pub max_entries: usize,
+    /// Time after which a cache entry is no longer used
+    pub ttl: Duration,
+    /// Optional persistent backend
+    pub store: Option<Box<dyn CacheStore>>,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl<R: DIDResolver> CachingDIDResolver<R> {
+    /// Wrap a resolver with an in-memory cache of the given size and time-to-live.
+    pub fn new(resolver: R, max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            resolver,
+            max_entries,
+            ttl,
+            store: None,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use a persistent backend in addition to the in-memory cache.
+    pub fn with_store(mut self, store: impl CacheStore + 'static) -> Self {
+        self.store = Some(Box::new(store));
+        self
+    }
+
+    /// Remove all in-memory cache entries.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    fn lookup(&self, key: &str) -> Option<ResolutionResult> {
+        if let Ok(mut entries) = self.entries.lock() {
+            match entries.get(key) {
+                Some(entry) if entry.is_fresh(self.ttl) => return Some(entry.result.clone()),
+                Some(_) => {
+                    entries.remove(key);
+                }
+                None => {}
+            }
+        }
+        let store = self.store.as_ref()?;
+        let entry = store.get(key)?;
+        if !entry.is_fresh(self.ttl) {
+            store.remove(key);
+            return None;
+        }
+        let result = entry.result.clone();
+        self.insert_memory(key, entry);
+        Some(result)
+    }
+
+    fn insert_memory(&self, key: &str, entry: CacheEntry) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        if !entries.contains_key(key) && entries.len() >= self.max_entries {
+            let ttl = self.ttl;
+            entries.retain(|_, entry| entry.is_fresh(ttl));
+            if entries.len() >= self.max_entries {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.stored_at)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(key.to_string(), entry);
+    }
+
+    fn remove(&self, key: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(key);
+        }
+        if let Some(store) = &self.store {
+            store.remove(key);
+        }
+    }
+
+    fn insert(&self, key: &str, result: ResolutionResult) {
+        let entry = CacheEntry {
+            stored_at: SystemTime::now(),
+            result,
+        };
+        if let Some(store) = &self.store {
+            store.put(key, &entry);
+        }
+        self.insert_memory(key, entry);
+    }
+}
+
+/// Get the cache key for a resolution request: the DID, with the requested version as DID
+/// parameters.
+fn cache_key(did: &str, input_metadata: &ResolutionInputMetadata) -> String {
+    let mut key = did.to_string();
+    let mut separator = '?';
+    if let Some(version_id) = &input_metadata.version_id {
+        key = format!("{key}{separator}versionId={version_id}");
+        separator = '&';
+    }
+    if let Some(version_time) = &input_metadata.version_time {
+        key = format!("{key}{separator}versionTime={}", version_time.to_rfc3339());
+    }
+    key
+}
+
+fn is_deactivated(doc_meta_opt: &Option<DocumentMetadata>) -> bool {
+    matches!(
+        doc_meta_opt,
+        Some(DocumentMetadata {
+            deactivated: Some(true),
+            ..
+        })
+    )
+}
+
+/// Whether a resolution result may be stored in the cache.
+fn is_cacheable(
+    res_meta: &ResolutionMetadata,
+    doc_opt: &Option<Document>,
+    doc_meta_opt: &Option<DocumentMetadata>,
+) -> bool {
+    res_meta.error.is_none() && doc_opt.is_some() && !is_deactivated(doc_meta_opt)
+}
+
+#[async_trait]
+impl<R: DIDResolver> DIDResolver for CachingDIDResolver<R> {
+    async fn resolve(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (
+        ResolutionMetadata,
+        Option<Document>,
+        Option<DocumentMetadata>,
+    ) {
+        let has_properties = input_metadata
+            .property_set
+            .as_ref()
+            .is_some_and(|property_set| !property_set.is_empty());
+        if has_properties {
+            return self.resolver.resolve(did, input_metadata).await;
+        }
+        let key = cache_key(did, input_metadata);
+        let no_cache = input_metadata.no_cache == Some(true);
+        if !no_cache {
+            if let Some(result) = self.lookup(&key) {
+                return result.into_parts();
+            }
+        }
+        let (res_meta, doc_opt, doc_meta_opt) = self.resolver.resolve(did, input_metadata).await;
+        if is_cacheable(&res_meta, &doc_opt, &doc_meta_opt) {
+            // A noCache request still refreshes the cache with the fresh result.
+            let result =
+                ResolutionResult::new(res_meta.clone(), doc_opt.clone(), doc_meta_opt.clone());
+            self.insert(&key, result);
+        } else if is_deactivated(&doc_meta_opt) {
+            self.remove(&key);
+        }
+        (res_meta, doc_opt, doc_meta_opt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did_resolve::Metadata;
+    use crate::error::ErrorCode;
+    use chrono::{TimeZone, Utc};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const DID: &str = "did:example:foo";
+
+    /// Resolver returning a fixed result and counting its calls
+    struct CountingResolver {
+        calls: AtomicUsize,
+        result: ResolutionResult,
+    }
+
+    impl CountingResolver {
+        fn new(result: ResolutionResult) -> Self {
+            Self {
+                calls: AtomicUsize::new(0),
+                result,
+            }
+        }
+
+        fn found() -> Self {
+            Self::new(ResolutionResult::new(
+                ResolutionMetadata::default(),
+                Some(Document::new(DID)),
+                Some(DocumentMetadata::default()),
+            ))
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl DIDResolver for CountingResolver {
+        async fn resolve(
+            &self,
+            _did: &str,
+            _input_metadata: &ResolutionInputMetadata,
+        ) -> (
+            ResolutionMetadata,
+            Option<Document>,
+            Option<DocumentMetadata>,
+        ) {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.result.clone().into_parts()
+        }
+    }
+
+    fn temp_cache_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ssi-dids-cache-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn caches_successful_result() {
+        let resolver = CachingDIDResolver::new(CountingResolver::found(), 10, DEFAULT_TTL);
+        let input_metadata = ResolutionInputMetadata::default();
+        for _ in 0..3 {
+            let (res_meta, doc_opt, _) = resolver.resolve(DID, &input_metadata).await;
+            assert!(res_meta.error.is_none());
+            assert_eq!(doc_opt.unwrap().id, DID);
+        }
+        assert_eq!(resolver.resolver.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_not_used() {
+        let resolver = CachingDIDResolver::new(CountingResolver::found(), 10, Duration::ZERO);
+        let input_metadata = ResolutionInputMetadata::default();
+        resolver.resolve(DID, &input_metadata).await;
+        resolver.resolve(DID, &input_metadata).await;
+        assert_eq!(resolver.resolver.calls(), 2);
+
+        let entry = CacheEntry {
+            stored_at: SystemTime::now() - Duration::from_secs(10),
+            result: ResolutionResult::default(),
+        };
+        assert!(!entry.is_fresh(Duration::from_secs(5)));
+        assert!(entry.is_fresh(Duration::from_secs(60)));
+    }
+
+    #[tokio::test]
+    async fn no_cache_bypasses_and_refreshes_cache() {
+        let resolver = CachingDIDResolver::new(CountingResolver::found(), 10, DEFAULT_TTL);
+        let no_cache = ResolutionInputMetadata {
+            no_cache: Some(true),
+            ..Default::default()
+        };
+        resolver.resolve(DID, &no_cache).await;
+        resolver.resolve(DID, &no_cache).await;
+        assert_eq!(resolver.resolver.calls(), 2);
+        // The noCache results were stored for later requests.
+        resolver
+            .resolve(DID, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(resolver.resolver.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn entries_are_keyed_by_version() {
+        let resolver = CachingDIDResolver::new(CountingResolver::found(), 10, DEFAULT_TTL);
+        let latest = ResolutionInputMetadata::default();
+        let version_1 = ResolutionInputMetadata {
+            version_id: Some("1".to_string()),
+            ..Default::default()
+        };
+        let version_time = ResolutionInputMetadata {
+            version_time: Some(Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        for input_metadata in [&latest, &version_1, &version_time, &version_1, &latest] {
+            resolver.resolve(DID, input_metadata).await;
+        }
+        assert_eq!(resolver.resolver.calls(), 3);
+
+        assert_eq!(cache_key(DID, &latest), DID);
+        assert_eq!(cache_key(DID, &version_1), format!("{DID}?versionId=1"));
+        assert_eq!(
+            cache_key(DID, &version_time),
+            format!("{DID}?versionTime=2022-01-01T00:00:00+00:00")
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_with_properties_are_not_cached() {
+        let resolver = CachingDIDResolver::new(CountingResolver::found(), 10, DEFAULT_TTL);
+        let with_property = ResolutionInputMetadata {
+            property_set: Some(HashMap::from([(
+                "network".to_string(),
+                Metadata::String("test".to_string()),
+            )])),
+            ..Default::default()
+        };
+        resolver.resolve(DID, &with_property).await;
+        resolver.resolve(DID, &with_property).await;
+        assert_eq!(resolver.resolver.calls(), 2);
+        // Nothing was stored for requests without the property.
+        resolver
+            .resolve(DID, &ResolutionInputMetadata::default())
+            .await;
+        assert_eq!(resolver.resolver.calls(), 3);
+        // An empty property set does not bypass the cache.
+        let empty = ResolutionInputMetadata {
+            property_set: Some(HashMap::new()),
+            ..Default::default()
+        };
+        resolver.resolve(DID, &empty).await;
+        assert_eq!(resolver.resolver.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn errors_are_not_cached() {
+        let result = ResolutionResult::new(
+            ResolutionMetadata::from_error(ErrorCode::NotFound),
+            None,
+            None,
+        );
+        let resolver = CachingDIDResolver::new(CountingResolver::new(result), 10, DEFAULT_TTL);
+        let input_metadata = ResolutionInputMetadata::default();
+        let (res_meta, _, _) = resolver.resolve(DID, &input_metadata).await;
+        assert_eq!(res_meta.error, Some(ErrorCode::NotFound));
+        resolver.resolve(DID, &input_metadata).await;
+        assert_eq!(resolver.resolver.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn deactivated_documents_are_not_cached() {
+        let result = ResolutionResult::new(
+            ResolutionMetadata::default(),
+            Some(Document::new(DID)),
+            Some(DocumentMetadata {
+                deactivated: Some(true),
+                ..Default::default()
+            }),
+        );
+        let resolver = CachingDIDResolver::new(CountingResolver::new(result), 10, DEFAULT_TTL);
+        let input_metadata = ResolutionInputMetadata::default();
+        resolver.resolve(DID, &input_metadata).await;
+        resolver.resolve(DID, &input_metadata).await;
+        assert_eq!(resolver.resolver.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn evicts_oldest_entry_when_full() {
+        let resolver = CachingDIDResolver::new(CountingResolver::found(), 2, DEFAULT_TTL);
+        let input_metadata = ResolutionInputMetadata::default();
+        for did in ["did:example:a", "did:example:b", "did:example:c"] {
+            resolver.resolve(did, &input_metadata).await;
+        }
+        assert_eq!(resolver.entries.lock().unwrap().len(), 2);
+        resolver.resolve("did:example:c", &input_metadata).await;
+        assert_eq!(resolver.resolver.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn file_store_is_shared_between_resolvers() {
+        let dir = temp_cache_dir("shared");
+        let store = FileCacheStore::new(&dir);
+        let input_metadata = ResolutionInputMetadata::default();
+        let first = CachingDIDResolver::new(CountingResolver::found(), 10, DEFAULT_TTL)
+            .with_store(FileCacheStore::new(&dir));
+        first.resolve(DID, &input_metadata).await;
+        assert!(store.path(DID).exists());
+
+        let second = CachingDIDResolver::new(CountingResolver::found(), 10, DEFAULT_TTL)
+            .with_store(FileCacheStore::new(&dir));
+        let (_, doc_opt, _) = second.resolve(DID, &input_metadata).await;
+        assert_eq!(doc_opt.unwrap().id, DID);
+        assert_eq!(second.resolver.calls(), 0);
+
+        // Expired entries are removed from the store.
+        let third = CachingDIDResolver::new(CountingResolver::found(), 10, Duration::ZERO)
+            .with_store(FileCacheStore::new(&dir));
+        assert!(third.lookup(DID).is_none());
+        assert!(!store.path(DID).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn file_store_escapes_key() {
+        let store = FileCacheStore::new("cache");
+        assert_eq!(
+            store.path("did:web:example.com%3A8080"),
+            PathBuf::from("cache/did_3aweb_3aexample.com_253A8080.json")
+        );
+    }
+}
@@ -0,0 +1,604 @@
+//! Verification of [DID controllers](https://www.w3.org/TR/did-core/#did-controller) and
+//! [`alsoKnownAs`](https://www.w3.org/TR/did-core/#also-known-as) claims
+//!
+//! A verification method is authorized for a DID and a verification relationship if the DID
+//! document lists it for that relationship, either embedded or by reference (possibly to a
+//! verification method in another DID document), or if it is authorized in the same way for one
+//! of the DID's controllers. Controller chains are followed up to a depth limit; controllers
+//! already visited, e.g. in a cycle, are not followed again.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use ssi_jwk::JWK;
+use thiserror::Error;
+
+use crate::{
+    absolute_did_url, did_method_name,
+    did_resolve::{DIDResolver, ResolutionInputMetadata},
+    error::ErrorCode,
+    Document, VerificationMethod, VerificationMethodMap, VerificationRelationship,
+};
+
+/// Default maximum number of controller links followed from the subject DID
+pub const DEFAULT_MAX_CONTROLLER_DEPTH: usize = 4;
+
+/// Error verifying a controller relationship or `alsoKnownAs` claim
+#[derive(Error, Debug, Clone)]
+pub enum ControllerError {
+    /// A DID needed for the verification could not be resolved
+    #[error("Unable to resolve {did}: {code}")]
+    Resolution { did: String, code: ErrorCode },
+    /// A DID needed for the verification is deactivated
+    #[error("DID is deactivated: {0}")]
+    Deactivated(String),
+    /// A resolved DID document is not for the DID that was resolved
+    #[error("DID document id {id} does not match resolved DID {did}")]
+    IdMismatch { did: String, id: String },
+    /// The verification method is not authorized for the DID and relationship
+    #[error("Verification method not authorized for {relationship} of {did}")]
+    NotAuthorized {
+        did: String,
+        relationship: &'static str,
+    },
+    /// The verification method was not found within the controller depth limit
+    #[error("Controller depth limit ({0}) exceeded")]
+    DepthLimitExceeded(usize),
+    /// A DID document does not list the alias in `alsoKnownAs`
+    #[error("{did} does not claim to also be known as {alias}")]
+    AlsoKnownAsNotClaimed { did: String, alias: String },
+    /// The alias is not a DID, so its reverse claim cannot be checked by DID resolution
+    #[error("Unable to verify alias that is not a DID: {0}")]
+    UnverifiableAlias(String),
+}
+
+impl ControllerError {
+    /// [Error code](ErrorCode) for the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Resolution { code, .. } => code.clone(),
+            Self::IdMismatch { .. } => ErrorCode::InternalError,
+            _ => ErrorCode::Unauthorized,
+        }
+    }
+}
+
+/// A verification method found to be authorized for a DID
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyAuthorization {
+    /// Absolute id of the verification method
+    pub id: String,
+    /// The verification method
+    pub verification_method: VerificationMethodMap,
+    /// DIDs through which the verification method is authorized, from the subject DID to the
+    /// DID whose document lists the verification method for the relationship
+    pub controller_chain: Vec<String>,
+}
+
+/// Resolve a DID to its DID document, rejecting deactivated DIDs and documents for other DIDs.
+async fn resolve_document(
+    resolver: &dyn DIDResolver,
+    did: &str,
+) -> Result<Document, ControllerError> {
+    let (res_meta, doc_opt, doc_meta_opt) = resolver
+        .resolve(did, &ResolutionInputMetadata::default())
+        .await;
+    let doc = match (res_meta.error, doc_opt) {
+        (None, Some(doc)) => doc,
+        (code, _) => {
+            return Err(ControllerError::Resolution {
+                did: did.to_string(),
+                code: code.unwrap_or(ErrorCode::NotFound),
+            })
+        }
+    };
+    if doc_meta_opt.and_then(|meta| meta.deactivated) == Some(true) {
+        return Err(ControllerError::Deactivated(did.to_string()));
+    }
+    if doc.id != did {
+        return Err(ControllerError::IdMismatch {
+            did: did.to_string(),
+            id: doc.id,
+        });
+    }
+    Ok(doc)
+}
+
+/// Get the DID of an absolute DID URL.
+fn did_of_url(did_url: &str) -> &str {
+    match did_url.find(['#', '?', '/']) {
+        Some(i) => &did_url[..i],
+        None => did_url,
+    }
+}
+
+/// DID documents resolved during a verification, so that each DID is resolved at most once
+struct Documents<'a> {
+    resolver: &'a dyn DIDResolver,
+    docs: HashMap<String, Result<Document, ControllerError>>,
+}
+
+impl<'a> Documents<'a> {
+    fn new(resolver: &'a dyn DIDResolver) -> Self {
+        Self {
+            resolver,
+            docs: HashMap::new(),
+        }
+    }
+
+    async fn get(&mut self, did: &str) -> &Result<Document, ControllerError> {
+        if !self.docs.contains_key(did) {
+            let doc = resolve_document(self.resolver, did).await;
+            self.docs.insert(did.to_string(), doc);
+        }
+        &self.docs[did]
+    }
+}
+
+/// Search the controller graph of a DID, breadth-first, for an authorized verification method
+/// accepted by `matches`.
+///
+/// If no match is found, an error encountered along the way (e.g. an unresolvable controller)
+/// is returned in preference to [ControllerError::NotAuthorized], since the outcome could
+/// otherwise have been different.
+async fn find_authorized(
+    resolver: &dyn DIDResolver,
+    did: &str,
+    rel: VerificationRelationship,
+    max_depth: usize,
+    matches: &dyn Fn(&str, &VerificationMethodMap) -> bool,
+) -> Result<KeyAuthorization, ControllerError> {
+    let mut docs = Documents::new(resolver);
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([vec![did.to_string()]]);
+    let mut error = None;
+    let mut depth_exceeded = false;
+    while let Some(chain) = queue.pop_front() {
+        let current = &chain[chain.len() - 1];
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+        let doc = match docs.get(current).await {
+            Ok(doc) => doc.clone(),
+            Err(err) if chain.len() == 1 => return Err(err.clone()),
+            Err(err) => {
+                error.get_or_insert_with(|| err.clone());
+                continue;
+            }
+        };
+        for vm in doc.get_relationship(rel) {
+            let (id, vmm) = match vm {
+                VerificationMethod::Map(vmm) => (vmm.get_id(&doc.id), vmm.clone()),
+                VerificationMethod::DIDURL(reference) => {
+                    let id = absolute_did_url(&doc.id, reference);
+                    let vm_did = did_of_url(&id);
+                    let vmm = if vm_did == doc.id {
+                        doc.find_verification_method(&id).cloned()
+                    } else {
+                        match docs.get(vm_did).await {
+                            Ok(vm_doc) => vm_doc.find_verification_method(&id).cloned(),
+                            Err(err) => {
+                                error.get_or_insert_with(|| err.clone());
+                                None
+                            }
+                        }
+                    };
+                    match vmm {
+                        Some(vmm) => (id, vmm),
+                        None => continue,
+                    }
+                }
+            };
+            if matches(&id, &vmm) {
+                return Ok(KeyAuthorization {
+                    id,
+                    verification_method: vmm,
+                    controller_chain: chain,
+                });
+            }
+        }
+        let controllers = doc.controller.iter().flat_map(|c| c.iter());
+        for controller in controllers.filter(|c| **c != doc.id && !visited.contains(*c)) {
+            if chain.len() > max_depth {
+                depth_exceeded = true;
+                break;
+            }
+            let mut controller_chain = chain.clone();
+            controller_chain.push(controller.clone());
+            queue.push_back(controller_chain);
+        }
+    }
+    if let Some(err) = error {
+        return Err(err);
+    }
+    if depth_exceeded {
+        return Err(ControllerError::DepthLimitExceeded(max_depth));
+    }
+    Err(ControllerError::NotAuthorized {
+        did: did.to_string(),
+        relationship: rel.property_name(),
+    })
+}
+
+/// Verify that a verification method is authorized for a DID and verification relationship,
+/// directly or through the DID's controllers.
+///
+/// `vm_id` may be relative to `did`. At most `max_depth` controller links are followed.
+pub async fn authorize_verification_method(
+    resolver: &dyn DIDResolver,
+    did: &str,
+    vm_id: &str,
+    rel: VerificationRelationship,
+    max_depth: usize,
+) -> Result<KeyAuthorization, ControllerError> {
+    let vm_id = absolute_did_url(did, vm_id);
+    find_authorized(resolver, did, rel, max_depth, &|id, _| id == vm_id).await
+}
+
+/// Find a verification method with the public key of a JWK that is authorized for a DID and
+/// verification relationship, directly or through the DID's controllers.
+///
+/// At most `max_depth` controller links are followed.
+pub async fn authorize_jwk(
+    resolver: &dyn DIDResolver,
+    did: &str,
+    jwk: &JWK,
+    rel: VerificationRelationship,
+    max_depth: usize,
+) -> Result<KeyAuthorization, ControllerError> {
+    find_authorized(resolver, did, rel, max_depth, &|_, vmm| vmm.match_jwk(jwk)).await
+}
+
+/// Verify an `alsoKnownAs` claim in both directions: the DID document of `did` must list
+/// `alias`, and the DID document of `alias` must list `did`.
+///
+/// `alias` must be a DID, since other identifiers cannot be checked by DID resolution.
+pub async fn verify_also_known_as(
+    resolver: &dyn DIDResolver,
+    did: &str,
+    alias: &str,
+) -> Result<(), ControllerError> {
+    let claims = |doc: &Document, other: &str| {
+        doc.also_known_as
+            .iter()
+            .flatten()
+            .any(|also_known_as| also_known_as == other)
+    };
+    let doc = resolve_document(resolver, did).await?;
+    if !claims(&doc, alias) {
+        return Err(ControllerError::AlsoKnownAsNotClaimed {
+            did: did.to_string(),
+            alias: alias.to_string(),
+        });
+    }
+    if did_method_name(alias).is_none() {
+        return Err(ControllerError::UnverifiableAlias(alias.to_string()));
+    }
+    let alias_doc = resolve_document(resolver, alias).await?;
+    if !claims(&alias_doc, did) {
+        return Err(ControllerError::AlsoKnownAsNotClaimed {
+            did: alias.to_string(),
+            alias: did.to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::{BundleEntry, StaticDIDResolver};
+    use crate::did_resolve::{DocumentMetadata, ResolutionMetadata, ResolutionResult};
+    use serde_json::{json, Value};
+
+    fn key_1() -> JWK {
+        serde_json::from_value(json!({
+            "kty": "EC",
+            "crv": "secp256k1",
+            "x": "WfY7Px6AgH6x-_dgAoRbg8weYRJA36ON-gQiFnETrqw",
+            "y": "IzFx3BUGztK0cyDStiunXbrZYYTtKbOUzx16SUK0sAY"
+        }))
+        .unwrap()
+    }
+
+    fn key_2() -> JWK {
+        serde_json::from_value(json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "x": "f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU",
+            "y": "x_FEzRjj6Ho9ZwG7ZVk_PXwW5tSgCYv6IHUKLtEkqFg"
+        }))
+        .unwrap()
+    }
+
+    fn verification_method(did: &str, name: &str, jwk: JWK) -> Value {
+        json!({
+            "id": format!("{did}#{name}"),
+            "type": "JsonWebKey2020",
+            "controller": did,
+            "publicKeyJwk": jwk
+        })
+    }
+
+    fn resolver(docs: &[Value]) -> StaticDIDResolver {
+        let mut resolver = StaticDIDResolver::new();
+        for doc in docs {
+            let doc: Document = serde_json::from_value(doc.clone()).unwrap();
+            let result = ResolutionResult::new(ResolutionMetadata::default(), Some(doc), None);
+            resolver.insert(
+                &result.did_document.as_ref().unwrap().id.clone(),
+                BundleEntry {
+                    result,
+                    versions: Vec::new(),
+                },
+            );
+        }
+        resolver
+    }
+
+    /// Controller chain `did:example:0` → `did:example:1` → … → `did:example:{len}`, with key 1
+    /// authorized only by the last DID.
+    fn controller_chain(len: usize) -> StaticDIDResolver {
+        let mut docs: Vec<Value> = (0..len)
+            .map(|i| {
+                json!({
+                    "id": format!("did:example:{i}"),
+                    "controller": format!("did:example:{}", i + 1)
+                })
+            })
+            .collect();
+        let last = format!("did:example:{len}");
+        docs.push(json!({
+            "id": last,
+            "assertionMethod": [verification_method(&last, "key-1", key_1())]
+        }));
+        resolver(&docs)
+    }
+
+    #[tokio::test]
+    async fn direct_authorization() {
+        let did = "did:example:a";
+        let resolver = resolver(&[json!({
+            "id": did,
+            "verificationMethod": [verification_method(did, "key-1", key_1())],
+            "authentication": ["#key-1"],
+            "assertionMethod": [verification_method(did, "key-2", key_2())]
+        })]);
+        let auth = authorize_verification_method(
+            &resolver,
+            did,
+            "#key-1",
+            VerificationRelationship::Authentication,
+            DEFAULT_MAX_CONTROLLER_DEPTH,
+        )
+        .await
+        .unwrap();
+        assert_eq!(auth.id, "did:example:a#key-1");
+        assert_eq!(auth.controller_chain, [did]);
+
+        let auth = authorize_jwk(
+            &resolver,
+            did,
+            &key_2(),
+            VerificationRelationship::AssertionMethod,
+            DEFAULT_MAX_CONTROLLER_DEPTH,
+        )
+        .await
+        .unwrap();
+        assert_eq!(auth.id, "did:example:a#key-2");
+
+        let err = authorize_verification_method(
+            &resolver,
+            did,
+            "#key-1",
+            VerificationRelationship::AssertionMethod,
+            DEFAULT_MAX_CONTROLLER_DEPTH,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ControllerError::NotAuthorized {
+                relationship: "assertionMethod",
+                ..
+            }
+        ));
+        assert_eq!(err.code(), ErrorCode::Unauthorized);
+    }
+
+    #[tokio::test]
+    async fn controller_chain_depth_limit() {
+        let resolver = controller_chain(3);
+        let auth = authorize_jwk(
+            &resolver,
+            "did:example:0",
+            &key_1(),
+            VerificationRelationship::AssertionMethod,
+            3,
+        )
+        .await
+        .unwrap();
+        assert_eq!(auth.id, "did:example:3#key-1");
+        assert_eq!(
+            auth.controller_chain,
+            [
+                "did:example:0",
+                "did:example:1",
+                "did:example:2",
+                "did:example:3"
+            ]
+        );
+
+        assert!(matches!(
+            authorize_jwk(
+                &resolver,
+                "did:example:0",
+                &key_1(),
+                VerificationRelationship::AssertionMethod,
+                2,
+            )
+            .await,
+            Err(ControllerError::DepthLimitExceeded(2))
+        ));
+        assert!(matches!(
+            authorize_verification_method(
+                &resolver,
+                "did:example:0",
+                "did:example:3#key-1",
+                VerificationRelationship::AssertionMethod,
+                0,
+            )
+            .await,
+            Err(ControllerError::DepthLimitExceeded(0))
+        ));
+    }
+
+    #[tokio::test]
+    async fn controller_cycle() {
+        let resolver = resolver(&[
+            json!({"id": "did:example:a", "controller": ["did:example:a", "did:example:b"]}),
+            json!({"id": "did:example:b", "controller": "did:example:c"}),
+            json!({"id": "did:example:c", "controller": "did:example:a"}),
+        ]);
+        assert!(matches!(
+            authorize_jwk(
+                &resolver,
+                "did:example:a",
+                &key_1(),
+                VerificationRelationship::Authentication,
+                100,
+            )
+            .await,
+            Err(ControllerError::NotAuthorized { did, .. }) if did == "did:example:a"
+        ));
+    }
+
+    #[tokio::test]
+    async fn cross_document_key_reference() {
+        let resolver = resolver(&[
+            json!({
+                "id": "did:example:a",
+                "capabilityInvocation": ["did:example:b#key-1", "did:example:missing#key-2"]
+            }),
+            json!({
+                "id": "did:example:b",
+                "verificationMethod": [verification_method("did:example:b", "key-1", key_1())]
+            }),
+        ]);
+        let auth = authorize_jwk(
+            &resolver,
+            "did:example:a",
+            &key_1(),
+            VerificationRelationship::CapabilityInvocation,
+            0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(auth.id, "did:example:b#key-1");
+        assert_eq!(auth.controller_chain, ["did:example:a"]);
+
+        // The referenced document is not resolvable, which is reported instead of
+        // NotAuthorized.
+        let err = authorize_jwk(
+            &resolver,
+            "did:example:a",
+            &key_2(),
+            VerificationRelationship::CapabilityInvocation,
+            0,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            &err,
+            ControllerError::Resolution { did, code: ErrorCode::NotFound } if did == "did:example:missing"
+        ));
+        assert_eq!(err.code(), ErrorCode::NotFound);
+    }
+
+    #[tokio::test]
+    async fn unusable_documents() {
+        let mut resolver = resolver(&[
+            json!({"id": "did:example:a", "controller": "did:example:missing"}),
+            json!({"id": "did:example:other"}),
+        ]);
+        assert!(matches!(
+            authorize_jwk(
+                &resolver,
+                "did:example:a",
+                &key_1(),
+                VerificationRelationship::Authentication,
+                DEFAULT_MAX_CONTROLLER_DEPTH,
+            )
+            .await,
+            Err(ControllerError::Resolution { did, .. }) if did == "did:example:missing"
+        ));
+
+        let entry = resolver.entries["did:example:other"].clone();
+        resolver.insert("did:example:b", entry.clone());
+        let err = authorize_jwk(
+            &resolver,
+            "did:example:b",
+            &key_1(),
+            VerificationRelationship::Authentication,
+            DEFAULT_MAX_CONTROLLER_DEPTH,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ControllerError::IdMismatch { .. }));
+        assert_eq!(err.code(), ErrorCode::InternalError);
+
+        let mut deactivated = entry;
+        deactivated.result.did_document = Some(Document::new("did:example:c"));
+        deactivated.result.did_document_metadata = Some(DocumentMetadata {
+            deactivated: Some(true),
+            ..Default::default()
+        });
+        resolver.insert("did:example:c", deactivated);
+        assert!(matches!(
+            authorize_jwk(
+                &resolver,
+                "did:example:c",
+                &key_1(),
+                VerificationRelationship::Authentication,
+                DEFAULT_MAX_CONTROLLER_DEPTH,
+            )
+            .await,
+            Err(ControllerError::Deactivated(did)) if did == "did:example:c"
+        ));
+    }
+
+    #[tokio::test]
+    async fn also_known_as_both_directions() {
+        let resolver = resolver(&[
+            json!({"id": "did:example:a", "alsoKnownAs": ["did:example:b", "https://example.com"]}),
+            json!({"id": "did:example:b", "alsoKnownAs": ["did:example:a"]}),
+            json!({"id": "did:example:c", "alsoKnownAs": ["did:example:a"]}),
+        ]);
+        verify_also_known_as(&resolver, "did:example:a", "did:example:b")
+            .await
+            .unwrap();
+        verify_also_known_as(&resolver, "did:example:b", "did:example:a")
+            .await
+            .unwrap();
+
+        // did:example:a does not claim did:example:c.
+        assert!(matches!(
+            verify_also_known_as(&resolver, "did:example:a", "did:example:c").await,
+            Err(ControllerError::AlsoKnownAsNotClaimed { did, alias })
+                if did == "did:example:a" && alias == "did:example:c"
+        ));
+        // did:example:c claims did:example:a, but not the other way around.
+        assert!(matches!(
+            verify_also_known_as(&resolver, "did:example:c", "did:example:a").await,
+            Err(ControllerError::AlsoKnownAsNotClaimed { did, alias })
+                if did == "did:example:a" && alias == "did:example:c"
+        ));
+        assert!(matches!(
+            verify_also_known_as(&resolver, "did:example:a", "https://example.com").await,
+            Err(ControllerError::UnverifiableAlias(_))
+        ));
+        assert!(matches!(
+            verify_also_known_as(&resolver, "did:example:missing", "did:example:a").await,
+            Err(ControllerError::Resolution { .. })
+        ));
+    }
+}
@@ -0,0 +1,648 @@
+//! [Well Known DID Configuration](https://identity.foundation/.well-known/resources/did-configuration/)
+//!
+//! Domain Linkage Credentials prove that the controller of a DID also controls a web origin.
+//! They are published by the origin in a [DID Configuration Resource](DIDConfiguration) at
+//! [WELL_KNOWN_PATH], and the DID links back to the origin with a
+//! [LinkedDomains](crate::service::LinkedDomains) service.
+//!
+//! Only the JWT format is supported for issuing and verifying credentials. Linked Data Proofs
+//! require RDF Dataset Canonicalization, which is not implemented; JSON-LD credentials in a DID
+//! Configuration Resource are parsed but fail verification with
+//! [UnsupportedProofType](DIDConfigurationError::UnsupportedProofType).
+
+use std::{collections::HashMap, path::PathBuf};
+
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use ssi_jwk::JWK;
+use thiserror::Error;
+
+use crate::{
+    controller::{authorize_verification_method, ControllerError, DEFAULT_MAX_CONTROLLER_DEPTH},
+    did_resolve::{DIDResolver, ResolutionInputMetadata},
+    error::{Error, ErrorCode},
+    Document, VerificationRelationship,
+};
+
+/// Path of the DID Configuration Resource on an origin
+pub const WELL_KNOWN_PATH: &str = "/.well-known/did-configuration.json";
+
+/// `@context` of the DID Configuration Resource, also used in Domain Linkage Credentials
+pub const DID_CONFIGURATION_V1_CONTEXT: &str =
+    "https://identity.foundation/.well-known/did-configuration/v1";
+
+/// `@context` of the W3C Verifiable Credentials Data Model v1
+pub const CREDENTIALS_V1_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+
+/// Credential type of a Domain Linkage Credential
+pub const DOMAIN_LINKAGE_CREDENTIAL: &str = "DomainLinkageCredential";
+
+/// Error issuing or verifying a Domain Linkage Credential
+#[derive(Error, Debug)]
+pub enum DIDConfigurationError {
+    #[error(transparent)]
+    JWS(#[from] ssi_jws::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Controller(#[from] ControllerError),
+    #[error(transparent)]
+    DID(#[from] Error),
+    /// The DID Configuration Resource could not be fetched
+    #[error("Unable to fetch DID configuration for {origin}: {message}")]
+    Fetch { origin: String, message: String },
+    /// The credential is not a valid Domain Linkage Credential
+    #[error("Invalid Domain Linkage Credential: {0}")]
+    InvalidCredential(String),
+    /// The credential is not valid at the current time
+    #[error("Domain Linkage Credential is expired or not yet valid")]
+    NotValidNow,
+    /// The credential is in JSON-LD format, with a Linked Data Proof of this type
+    #[error("Unsupported Domain Linkage Credential proof type: {0}")]
+    UnsupportedProofType(String),
+    /// The verification method has no `publicKeyJwk`
+    #[error("Verification method has no publicKeyJwk: {0}")]
+    MissingKey(String),
+    /// The DID has no LinkedDomains service listing the origin
+    #[error("DID {did} does not list {origin} in a LinkedDomains service")]
+    OriginNotLinked { did: String, origin: String },
+    /// No valid Domain Linkage Credential for the DID was found for the origin
+    #[error("No valid Domain Linkage Credential for {did} at {origin}")]
+    NoValidCredential { did: String, origin: String },
+}
+
+/// [DID Configuration Resource](https://identity.foundation/.well-known/resources/did-configuration/#did-configuration-resource)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DIDConfiguration {
+    #[serde(rename = "@context")]
+    pub context: String,
+    /// Domain Linkage Credentials
+    pub linked_dids: Vec<LinkedDid>,
+}
+
+/// Domain Linkage Credential in a [DIDConfiguration], in either format
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum LinkedDid {
+    /// [JWT](https://identity.foundation/.well-known/resources/did-configuration/#json-web-token-proof-format)
+    Jwt(String),
+    /// [JSON-LD](https://identity.foundation/.well-known/resources/did-configuration/#linked-data-proof-format),
+    /// which can not be verified
+    JsonLd(Box<DomainLinkageCredential>),
+}
+
+/// [Domain Linkage Credential](https://identity.foundation/.well-known/resources/did-configuration/#domain-linkage-credential)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainLinkageCredential {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "type")]
+    pub type_: Vec<String>,
+    /// DID of the issuer, which is also the credential subject
+    pub issuer: String,
+    pub issuance_date: DateTime<Utc>,
+    pub expiration_date: DateTime<Utc>,
+    pub credential_subject: DomainLinkageSubject,
+    /// Proof, for the JSON-LD format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<Proof>,
+}
+
+/// Subject of a [DomainLinkageCredential]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DomainLinkageSubject {
+    /// The linked DID
+    pub id: String,
+    /// The linked origin
+    pub origin: String,
+}
+
+/// Linked Data Proof of a JSON-LD [DomainLinkageCredential]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Proof {
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(flatten)]
+    pub property_set: Option<Map<String, Value>>,
+}
+
+/// JWT claims of a Domain Linkage Credential in JWT format
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct JwtClaims {
+    #[serde(rename = "iss")]
+    issuer: String,
+    #[serde(rename = "sub")]
+    subject: String,
+    #[serde(rename = "nbf")]
+    not_before: i64,
+    #[serde(rename = "exp")]
+    expiration: i64,
+    vc: DomainLinkageCredential,
+}
+
+impl DIDConfiguration {
+    /// Construct a DID Configuration Resource.
+    pub fn new(linked_dids: Vec<LinkedDid>) -> Self {
+        Self {
+            context: DID_CONFIGURATION_V1_CONTEXT.to_string(),
+            linked_dids,
+        }
+    }
+}
+
+impl DomainLinkageCredential {
+    /// Construct an unsigned Domain Linkage Credential linking a DID and an origin.
+    ///
+    /// Dates are truncated to whole seconds.
+    pub fn new(
+        did: &str,
+        origin: &str,
+        issuance_date: DateTime<Utc>,
+        expiration_date: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            context: vec![
+                CREDENTIALS_V1_CONTEXT.to_string(),
+                DID_CONFIGURATION_V1_CONTEXT.to_string(),
+            ],
+            type_: vec![
+                "VerifiableCredential".to_string(),
+                DOMAIN_LINKAGE_CREDENTIAL.to_string(),
+            ],
+            issuer: did.to_string(),
+            issuance_date: issuance_date.trunc_subsecs(0),
+            expiration_date: expiration_date.trunc_subsecs(0),
+            credential_subject: DomainLinkageSubject {
+                id: did.to_string(),
+                origin: origin.to_string(),
+            },
+            proof: None,
+        }
+    }
+
+    /// Issue the credential as a JWT, signed with the key of the verification method
+    /// `verification_method`.
+    pub fn to_jwt(
+        &self,
+        key: &JWK,
+        verification_method: &str,
+    ) -> Result<String, DIDConfigurationError> {
+        let mut vc = self.clone();
+        vc.proof = None;
+        let claims = JwtClaims {
+            issuer: self.issuer.clone(),
+            subject: self.credential_subject.id.clone(),
+            not_before: self.issuance_date.timestamp(),
+            expiration: self.expiration_date.timestamp(),
+            vc,
+        };
+        let mut header = ssi_jws::Header::new(ssi_jws::algorithm_for_jwk(key)?);
+        header.key_id = Some(verification_method.to_string());
+        let payload = serde_json::to_string(&claims)?;
+        Ok(ssi_jws::encode_sign_custom_header(&payload, key, &header)?)
+    }
+
+    /// Issue the credential as a JWT, signed with a key of the issuer's DID document.
+    ///
+    /// The verification method is the first one authorized for `assertionMethod` with the key's
+    /// public key.
+    pub fn issue_jwt(
+        &self,
+        document: &Document,
+        key: &JWK,
+    ) -> Result<String, DIDConfigurationError> {
+        let verification_method =
+            document.select_verification_method(VerificationRelationship::AssertionMethod, key)?;
+        self.to_jwt(key, &verification_method.get_id(&document.id))
+    }
+
+    /// Check the credential claims for a DID and origin, without checking the signature.
+    fn check_claims(&self, did: &str, origin: &str) -> Result<(), DIDConfigurationError> {
+        let invalid = |message: &str| DIDConfigurationError::InvalidCredential(message.to_string());
+        if !self
+            .type_
+            .iter()
+            .any(|type_| type_ == DOMAIN_LINKAGE_CREDENTIAL)
+        {
+            return Err(invalid("missing DomainLinkageCredential type"));
+        }
+        if !self
+            .context
+            .iter()
+            .any(|context| context == DID_CONFIGURATION_V1_CONTEXT)
+        {
+            return Err(invalid("missing DID configuration context"));
+        }
+        if self.issuer != did || self.credential_subject.id != did {
+            return Err(invalid("issuer and credential subject must be the DID"));
+        }
+        if self.credential_subject.origin.trim_end_matches('/') != origin.trim_end_matches('/') {
+            return Err(invalid("credential subject origin does not match"));
+        }
+        let now = Utc::now();
+        if now < self.issuance_date || now >= self.expiration_date {
+            return Err(DIDConfigurationError::NotValidNow);
+        }
+        Ok(())
+    }
+}
+
+/// Get the public key of a verification method authorized for `assertionMethod` by a DID.
+async fn assertion_key(
+    resolver: &dyn DIDResolver,
+    did: &str,
+    verification_method: &str,
+) -> Result<JWK, DIDConfigurationError> {
+    let authorization = authorize_verification_method(
+        resolver,
+        did,
+        verification_method,
+        VerificationRelationship::AssertionMethod,
+        DEFAULT_MAX_CONTROLLER_DEPTH,
+    )
+    .await?;
+    authorization
+        .verification_method
+        .public_key_jwk
+        .ok_or(DIDConfigurationError::MissingKey(authorization.id))
+}
+
+impl LinkedDid {
+    /// Verify the credential as a Domain Linkage Credential for a DID and origin.
+    ///
+    /// The signing key must be authorized for `assertionMethod` by the DID.
+    pub async fn verify(
+        &self,
+        resolver: &dyn DIDResolver,
+        did: &str,
+        origin: &str,
+    ) -> Result<(), DIDConfigurationError> {
+        match self {
+            Self::Jwt(jwt) => verify_jwt(resolver, jwt, did, origin).await,
+            Self::JsonLd(credential) => Err(DIDConfigurationError::UnsupportedProofType(
+                credential
+                    .proof
+                    .as_ref()
+                    .map_or("none", |proof| proof.type_.as_str())
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// DID of the issuer, if the credential can be decoded
+    pub fn issuer(&self) -> Option<String> {
+        match self {
+            Self::Jwt(jwt) => {
+                let (_, payload) = ssi_jws::decode_unverified(jwt).ok()?;
+                let claims: JwtClaims = serde_json::from_slice(&payload).ok()?;
+                Some(claims.issuer)
+            }
+            Self::JsonLd(credential) => Some(credential.issuer.clone()),
+        }
+    }
+}
+
+async fn verify_jwt(
+    resolver: &dyn DIDResolver,
+    jwt: &str,
+    did: &str,
+    origin: &str,
+) -> Result<(), DIDConfigurationError> {
+    let (header, payload) = ssi_jws::decode_unverified(jwt)?;
+    let claims: JwtClaims = serde_json::from_slice(&payload)?;
+    let invalid = |message: &str| DIDConfigurationError::InvalidCredential(message.to_string());
+    if claims.issuer != did || claims.subject != did {
+        return Err(invalid("iss and sub must be the DID"));
+    }
+    if claims.not_before != claims.vc.issuance_date.timestamp()
+        || claims.expiration != claims.vc.expiration_date.timestamp()
+    {
+        return Err(invalid("nbf and exp must match the credential dates"));
+    }
+    claims.vc.check_claims(did, origin)?;
+    let key_id = header.key_id.ok_or_else(|| invalid("missing kid"))?;
+    if !key_id.starts_with(&format!("{did}#")) {
+        return Err(invalid("kid must be a verification method of the DID"));
+    }
+    let key = assertion_key(resolver, did, &key_id).await?;
+    ssi_jws::decode_verify(jwt, &key)?;
+    Ok(())
+}
+
+/// Source of DID Configuration Resources by origin
+///
+/// [HTTPConfigurationSource] fetches them from the web; [FileConfigurationSource] reads them
+/// from local files, e.g. for tests.
+#[async_trait]
+pub trait ConfigurationSource: Sync {
+    /// Get the DID Configuration Resource of an origin.
+    async fn fetch(&self, origin: &str) -> Result<DIDConfiguration, DIDConfigurationError>;
+}
+
+/// [ConfigurationSource] fetching [WELL_KNOWN_PATH] from the origin over HTTP(S)
+#[derive(Debug, Clone, Default)]
+pub struct HTTPConfigurationSource;
+
+#[async_trait]
+impl ConfigurationSource for HTTPConfigurationSource {
+    async fn fetch(&self, origin: &str) -> Result<DIDConfiguration, DIDConfigurationError> {
+        let fetch_error = |message: String| DIDConfigurationError::Fetch {
+            origin: origin.to_string(),
+            message,
+        };
+        let url = format!("{}{WELL_KNOWN_PATH}", origin.trim_end_matches('/'));
+        let resp = reqwest::get(&url)
+            .await
+            .map_err(|e| fetch_error(e.to_string()))?;
+        let resp = resp
+            .error_for_status()
+            .map_err(|e| fetch_error(e.to_string()))?;
+        let body = resp.bytes().await.map_err(|e| fetch_error(e.to_string()))?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+/// [ConfigurationSource] reading DID Configuration Resources from local files, by origin
+#[derive(Debug, Clone, Default)]
+pub struct FileConfigurationSource {
+    pub files: HashMap<String, PathBuf>,
+}
+
+impl FileConfigurationSource {
+    /// Construct a source with no files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the file to read for an origin.
+    pub fn insert(&mut self, origin: &str, path: impl Into<PathBuf>) {
+        self.files
+            .insert(origin.trim_end_matches('/').to_string(), path.into());
+    }
+}
+
+#[async_trait]
+impl ConfigurationSource for FileConfigurationSource {
+    async fn fetch(&self, origin: &str) -> Result<DIDConfiguration, DIDConfigurationError> {
+        let fetch_error = |message: String| DIDConfigurationError::Fetch {
+            origin: origin.to_string(),
+            message,
+        };
+        let path = self
+            .files
+            .get(origin.trim_end_matches('/'))
+            .ok_or_else(|| fetch_error("no file for origin".to_string()))?;
+        let bytes = std::fs::read(path).map_err(|e| fetch_error(e.to_string()))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+/// Verify a DID Configuration Resource of an origin for a DID.
+///
+/// The DID must list the origin in a LinkedDomains service, and the resource must contain a
+/// valid Domain Linkage Credential for the DID and origin.
+pub async fn verify_configuration(
+    resolver: &dyn DIDResolver,
+    configuration: &DIDConfiguration,
+    did: &str,
+    origin: &str,
+) -> Result<(), DIDConfigurationError> {
+    let origins = linked_origins(resolver, did).await?;
+    if !origins
+        .iter()
+        .any(|linked| linked.trim_end_matches('/') == origin.trim_end_matches('/'))
+    {
+        return Err(DIDConfigurationError::OriginNotLinked {
+            did: did.to_string(),
+            origin: origin.to_string(),
+        });
+    }
+    let mut error = None;
+    for linked_did in &configuration.linked_dids {
+        if linked_did.issuer().as_deref() != Some(did) {
+            continue;
+        }
+        match linked_did.verify(resolver, did, origin).await {
+            Ok(()) => return Ok(()),
+            Err(err) => error = Some(err),
+        }
+    }
+    Err(error.unwrap_or(DIDConfigurationError::NoValidCredential {
+        did: did.to_string(),
+        origin: origin.to_string(),
+    }))
+}
+
+/// Get the origins listed in the LinkedDomains services of a DID.
+async fn linked_origins(
+    resolver: &dyn DIDResolver,
+    did: &str,
+) -> Result<Vec<String>, DIDConfigurationError> {
+    let (res_meta, doc_opt, _) = resolver
+        .resolve(did, &ResolutionInputMetadata::default())
+        .await;
+    let doc = match (res_meta.error, doc_opt) {
+        (None, Some(doc)) => doc,
+        (code, _) => {
+            return Err(ControllerError::Resolution {
+                did: did.to_string(),
+                code: code.unwrap_or(ErrorCode::NotFound),
+            }
+            .into())
+        }
+    };
+    Ok(doc
+        .linked_domains()
+        .into_iter()
+        .flat_map(|service| service.origins)
+        .collect())
+}
+
+/// Result of verifying the link between a DID and one of its linked origins
+#[derive(Debug)]
+pub struct DomainVerification {
+    pub origin: String,
+    pub result: Result<(), DIDConfigurationError>,
+}
+
+/// Verify all origins listed in the LinkedDomains services of a DID, fetching their DID
+/// Configuration Resources from `source`.
+pub async fn verify_linked_domains(
+    resolver: &dyn DIDResolver,
+    source: &dyn ConfigurationSource,
+    did: &str,
+) -> Result<Vec<DomainVerification>, DIDConfigurationError> {
+    let mut verifications = Vec::new();
+    for origin in linked_origins(resolver, did).await? {
+        let result = match source.fetch(&origin).await {
+            Ok(configuration) => verify_configuration(resolver, &configuration, did, &origin).await,
+            Err(err) => Err(err),
+        };
+        verifications.push(DomainVerification { origin, result });
+    }
+    Ok(verifications)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did_resolve::{DocumentMetadata, ResolutionMetadata};
+    use chrono::Duration;
+    use serde_json::json;
+
+    const DID: &str = "did:example:linked";
+    const ORIGIN: &str = "https://example.com";
+
+    /// Resolver of a single DID document
+    struct TestResolver(Document);
+
+    #[async_trait]
+    impl DIDResolver for TestResolver {
+        async fn resolve(
+            &self,
+            did: &str,
+            _input_metadata: &ResolutionInputMetadata,
+        ) -> (
+            ResolutionMetadata,
+            Option<Document>,
+            Option<DocumentMetadata>,
+        ) {
+            if did != self.0.id {
+                return (
+                    ResolutionMetadata::from_error(ErrorCode::NotFound),
+                    None,
+                    None,
+                );
+            }
+            (
+                ResolutionMetadata::default(),
+                Some(self.0.clone()),
+                Some(DocumentMetadata::default()),
+            )
+        }
+    }
+
+    fn setup() -> (JWK, TestResolver) {
+        let key = JWK::generate_secp256k1().unwrap();
+        let doc: Document = serde_json::from_value(json!({
+            "@context": "https://www.w3.org/ns/did/v1",
+            "id": DID,
+            "verificationMethod": [{
+                "id": "#key-1",
+                "type": "JsonWebKey2020",
+                "controller": DID,
+                "publicKeyJwk": key.to_public(),
+            }],
+            "assertionMethod": ["#key-1"],
+            "service": [{
+                "id": "#domain",
+                "type": "LinkedDomains",
+                "serviceEndpoint": ORIGIN,
+            }],
+        }))
+        .unwrap();
+        (key, TestResolver(doc))
+    }
+
+    fn credential(origin: &str) -> DomainLinkageCredential {
+        let now = Utc::now();
+        DomainLinkageCredential::new(
+            DID,
+            origin,
+            now - Duration::days(1),
+            now + Duration::days(1),
+        )
+    }
+
+    #[tokio::test]
+    async fn jwt_credential_round_trip() {
+        let (key, resolver) = setup();
+        let jwt = credential(ORIGIN).issue_jwt(&resolver.0, &key).unwrap();
+        let (header, _) = ssi_jws::decode_unverified(&jwt).unwrap();
+        assert_eq!(header.key_id.as_deref(), Some("did:example:linked#key-1"));
+        let configuration = DIDConfiguration::new(vec![LinkedDid::Jwt(jwt)]);
+        verify_configuration(&resolver, &configuration, DID, ORIGIN)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn issue_with_unlisted_key_fails() {
+        let (_, resolver) = setup();
+        let other_key = JWK::generate_secp256k1().unwrap();
+        let err = credential(ORIGIN)
+            .issue_jwt(&resolver.0, &other_key)
+            .unwrap_err();
+        assert!(
+            matches!(err, DIDConfigurationError::DID(Error::KeyMismatch)),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn json_ld_credential_is_unsupported() {
+        let (_, resolver) = setup();
+        let configuration: DIDConfiguration = serde_json::from_value(json!({
+            "@context": DID_CONFIGURATION_V1_CONTEXT,
+            "linked_dids": [{
+                "@context": [CREDENTIALS_V1_CONTEXT, DID_CONFIGURATION_V1_CONTEXT],
+                "type": ["VerifiableCredential", DOMAIN_LINKAGE_CREDENTIAL],
+                "issuer": DID,
+                "issuanceDate": "2020-12-04T14:08:28-06:00",
+                "expirationDate": "2099-12-04T14:08:28-06:00",
+                "credentialSubject": { "id": DID, "origin": ORIGIN },
+                "proof": {
+                    "type": "Ed25519Signature2018",
+                    "created": "2020-12-04T20:08:28.540Z",
+                    "jws": "eyJhbGciOiJFZERTQSIsImI2NCI6ZmFsc2UsImNyaXQiOlsiYjY0Il19..",
+                    "proofPurpose": "assertionMethod",
+                    "verificationMethod": "did:example:linked#key-1"
+                }
+            }]
+        }))
+        .unwrap();
+        let err = verify_configuration(&resolver, &configuration, DID, ORIGIN)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, DIDConfigurationError::UnsupportedProofType(type_) if type_ == "Ed25519Signature2018"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn tampered_jwt_credential_fails() {
+        let (key, resolver) = setup();
+        let jwt = credential(ORIGIN).issue_jwt(&resolver.0, &key).unwrap();
+        let other = credential("https://other.example")
+            .issue_jwt(&resolver.0, &key)
+            .unwrap();
+        // Signature of one credential over the header and payload of the other
+        let (header_payload, _) = jwt.rsplit_once('.').unwrap();
+        let (_, signature) = other.rsplit_once('.').unwrap();
+        let tampered = LinkedDid::Jwt(format!("{header_payload}.{signature}"));
+        assert!(tampered.verify(&resolver, DID, ORIGIN).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unlinked_origin_fails() {
+        let (key, resolver) = setup();
+        let jwt = credential("https://other.example")
+            .issue_jwt(&resolver.0, &key)
+            .unwrap();
+        let configuration = DIDConfiguration::new(vec![LinkedDid::Jwt(jwt)]);
+        let err = verify_configuration(&resolver, &configuration, DID, "https://other.example")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DIDConfigurationError::OriginNotLinked { .. }),
+            "{}",
+            err
+        );
+    }
+}
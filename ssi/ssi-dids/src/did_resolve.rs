@@ -1,6 +1,25 @@
 use std::collections::HashMap;
 
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::{Error, ErrorCode},
+    representation::Representation,
+    Document,
+};
+
+/// Media type for the JSON-LD representation of a DID document
+pub const TYPE_DID_LD_JSON: &str = "application/did+ld+json";
+
+/// Media type for a [DID Resolution Result](https://w3c-ccg.github.io/did-resolution/#did-resolution-result)
+pub const TYPE_DID_RESOLUTION: &str =
+    "application/ld+json;profile=\"https://w3id.org/did-resolution\";charset=utf-8";
+
+/// `@context` value for a DID Resolution Result
+pub const DID_RESOLUTION_V1_CONTEXT: &str = "https://w3id.org/did-resolution/v1";
 
 /// [Metadata structure](https://www.w3.org/TR/did-core/#metadata-structure) "for DID resolution,
 /// DID URL dereferencing, and other DID-related processes"
@@ -15,10 +34,326 @@ pub enum Metadata {
     List(Vec<Metadata>),
     /// [Boolean](https://infra.spec.whatwg.org/#boolean)
     Boolean(bool),
+    /// Number, for properties such as `maxAge`
+    Number(serde_json::Number),
     /// [Null](https://infra.spec.whatwg.org/#nulls)
     Null,
 }
 
+impl From<Metadata> for Value {
+    fn from(metadata: Metadata) -> Self {
+        match metadata {
+            Metadata::String(string) => Value::String(string),
+            Metadata::Map(map) => Value::Object(
+                map.into_iter()
+                    .map(|(name, value)| (name, Value::from(value)))
+                    .collect(),
+            ),
+            Metadata::List(list) => Value::Array(list.into_iter().map(Value::from).collect()),
+            Metadata::Boolean(boolean) => Value::Bool(boolean),
+            Metadata::Number(number) => Value::Number(number),
+            Metadata::Null => Value::Null,
+        }
+    }
+}
+
+impl From<Value> for Metadata {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::String(string) => Metadata::String(string),
+            Value::Object(map) => Metadata::Map(
+                map.into_iter()
+                    .map(|(name, value)| (name, Metadata::from(value)))
+                    .collect(),
+            ),
+            Value::Array(list) => Metadata::List(list.into_iter().map(Metadata::from).collect()),
+            Value::Bool(boolean) => Metadata::Boolean(boolean),
+            Value::Number(number) => Metadata::Number(number),
+            Value::Null => Metadata::Null,
+        }
+    }
+}
+
+/// [DID Resolution Input Metadata](https://www.w3.org/TR/did-core/#did-resolution-input-metadata)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionInputMetadata {
+    /// Media type of the preferred representation of the DID document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept: Option<String>,
+
+    /// Request that caching be disabled and a fresh result be returned
+    ///
+    /// Defined in [DID Resolution §Input Metadata](https://w3c-ccg.github.io/did-resolution/#caching).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_cache: Option<bool>,
+
+    /// Request a specific version of the DID document
+    ///
+    /// [`versionId`](https://www.w3.org/TR/did-spec-registries/#versionId-param) as defined for
+    /// DID parameters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+
+    /// Request the version of the DID document that was current at a point in time
+    ///
+    /// [`versionTime`](https://www.w3.org/TR/did-spec-registries/#versionTime-param) as defined
+    /// for DID parameters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_time: Option<DateTime<Utc>>,
+
+    /// Additional input metadata properties
+    #[serde(flatten)]
+    pub property_set: Option<HashMap<String, Metadata>>,
+}
+
+/// [DID Resolution Metadata](https://www.w3.org/TR/did-core/#did-resolution-metadata)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionMetadata {
+    /// Error code, if resolution was not successful
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorCode>,
+
+    /// Media type of the returned representation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    /// Additional resolution metadata properties
+    #[serde(flatten)]
+    pub property_set: Option<HashMap<String, Metadata>>,
+}
+
+impl ResolutionMetadata {
+    /// Construct resolution metadata for a failed resolution.
+    pub fn from_error(error: ErrorCode) -> Self {
+        Self {
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+}
+
+/// [DID Document Metadata](https://www.w3.org/TR/did-core/#did-document-metadata)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentMetadata {
+    /// [`created`](https://www.w3.org/TR/did-core/#dfn-created)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+
+    /// [`updated`](https://www.w3.org/TR/did-core/#dfn-updated)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Utc>>,
+
+    /// [`deactivated`](https://www.w3.org/TR/did-core/#dfn-deactivated)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deactivated: Option<bool>,
+
+    /// [`versionId`](https://www.w3.org/TR/did-core/#dfn-versionid) of the resolved version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+
+    /// [`nextVersionId`](https://www.w3.org/TR/did-core/#dfn-nextversionid): version id of the
+    /// next version, if the resolved version is not the latest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_version_id: Option<String>,
+
+    /// Additional document metadata properties
+    #[serde(flatten)]
+    pub property_set: Option<HashMap<String, Metadata>>,
+}
+
+impl ResolutionInputMetadata {
+    /// Whether a specific version of the DID document is requested, by `versionId` or
+    /// `versionTime`.
+    pub fn is_versioned(&self) -> bool {
+        self.version_id.is_some() || self.version_time.is_some()
+    }
+
+    /// Whether document metadata confirms the requested version: true if no version is
+    /// requested, or if the metadata [matches](DocumentMetadata::matches_version) it.
+    pub fn is_version_confirmed(&self, doc_meta: Option<&DocumentMetadata>) -> bool {
+        !self.is_versioned() || doc_meta.is_some_and(|doc_meta| doc_meta.matches_version(self))
+    }
+}
+
+impl DocumentMetadata {
+    /// Time from which this version of the DID document was current: `updated`, or `created`
+    /// for the first version
+    pub fn version_start(&self) -> Option<DateTime<Utc>> {
+        self.updated.or(self.created)
+    }
+
+    /// Whether the metadata describes a version of the DID document that satisfies the
+    /// `versionId` and `versionTime` of the input metadata.
+    ///
+    /// A version satisfies `versionTime` if it became current at or before that time; choosing
+    /// the latest such version is up to the caller. Versions without a known start time never
+    /// satisfy `versionTime`.
+    pub fn matches_version(&self, input_metadata: &ResolutionInputMetadata) -> bool {
+        if let Some(version_id) = &input_metadata.version_id {
+            if self.version_id.as_ref() != Some(version_id) {
+                return false;
+            }
+        }
+        if let Some(version_time) = input_metadata.version_time {
+            match self.version_start() {
+                Some(start) if start <= version_time => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// [DID Resolution Result](https://w3c-ccg.github.io/did-resolution/#did-resolution-result)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionResult {
+    #[serde(rename = "@context")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<Value>,
+
+    /// Resolved DID document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub did_document: Option<Document>,
+
+    /// Resolution metadata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub did_resolution_metadata: Option<ResolutionMetadata>,
+
+    /// Document metadata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub did_document_metadata: Option<DocumentMetadata>,
+
+    /// Additional properties
+    #[serde(flatten)]
+    pub property_set: Option<HashMap<String, Value>>,
+}
+
+impl ResolutionResult {
+    /// Construct a resolution result from the return values of [DIDResolver::resolve].
+    pub fn new(
+        res_meta: ResolutionMetadata,
+        doc_opt: Option<Document>,
+        doc_meta_opt: Option<DocumentMetadata>,
+    ) -> Self {
+        Self {
+            context: Some(Value::String(DID_RESOLUTION_V1_CONTEXT.to_string())),
+            did_document: doc_opt,
+            did_resolution_metadata: Some(res_meta),
+            did_document_metadata: doc_meta_opt,
+            property_set: None,
+        }
+    }
+
+    /// Split into the return values of [DIDResolver::resolve].
+    pub fn into_parts(
+        self,
+    ) -> (
+        ResolutionMetadata,
+        Option<Document>,
+        Option<DocumentMetadata>,
+    ) {
+        (
+            self.did_resolution_metadata.unwrap_or_default(),
+            self.did_document,
+            self.did_document_metadata,
+        )
+    }
+}
+
+/// A [DID resolver](https://www.w3.org/TR/did-core/#dfn-did-resolvers), implementing the
+/// [DID Resolution](https://www.w3.org/TR/did-core/#did-resolution) `resolve` function.
+#[async_trait]
+pub trait DIDResolver: Sync {
+    /// Resolve a DID
+    ///
+    /// i.e. the `resolve` function from [DID
+    /// Core](https://www.w3.org/TR/did-core/#did-resolution) and [DID
+    /// Resolution](https://w3c-ccg.github.io/did-resolution/#resolving).
+    async fn resolve(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (
+        ResolutionMetadata,
+        Option<Document>,
+        Option<DocumentMetadata>,
+    );
+
+    /// Resolve a DID to a representation of its DID document
+    ///
+    /// i.e. the `resolveRepresentation` function from [DID
+    /// Core](https://www.w3.org/TR/did-core/#did-resolution). The representation is selected by
+    /// the `accept` input metadata property; see [Representation::from_accept].
+    async fn resolve_representation(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (ResolutionMetadata, Vec<u8>, Option<DocumentMetadata>) {
+        let representation = match Representation::from_accept(input_metadata.accept.as_deref()) {
+            Ok(representation) => representation,
+            Err(_) => {
+                return (
+                    ResolutionMetadata::from_error(ErrorCode::RepresentationNotSupported),
+                    Vec::new(),
+                    None,
+                )
+            }
+        };
+        let (mut res_meta, doc_opt, doc_meta_opt) = self.resolve(did, input_metadata).await;
+        if res_meta.error.is_some() {
+            return (res_meta, Vec::new(), doc_meta_opt);
+        }
+        let doc = match doc_opt {
+            Some(doc) => doc,
+            None => {
+                return (
+                    ResolutionMetadata::from_error(ErrorCode::NotFound),
+                    Vec::new(),
+                    doc_meta_opt,
+                )
+            }
+        };
+        match doc.to_representation(representation) {
+            Ok(bytes) => {
+                res_meta.content_type = Some(representation.media_type().to_string());
+                (res_meta, bytes, doc_meta_opt)
+            }
+            Err(_) => (
+                ResolutionMetadata::from_error(ErrorCode::InternalError),
+                Vec::new(),
+                doc_meta_opt,
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: DIDResolver + ?Sized> DIDResolver for &T {
+    async fn resolve(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (
+        ResolutionMetadata,
+        Option<Document>,
+        Option<DocumentMetadata>,
+    ) {
+        (**self).resolve(did, input_metadata).await
+    }
+
+    async fn resolve_representation(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (ResolutionMetadata, Vec<u8>, Option<DocumentMetadata>) {
+        (**self).resolve_representation(did, input_metadata).await
+    }
+}
+
 /// A DID Resolver implementing a client for the [DID Resolution HTTP(S)
 /// Binding](https://w3c-ccg.github.io/did-resolution/#bindings-https).
 pub struct HTTPDIDResolver {
@@ -33,4 +368,117 @@ impl HTTPDIDResolver {
             endpoint: url.to_string(),
         }
     }
+
+    /// Send a resolution request for a DID to the endpoint, passing `noCache` as a
+    /// `Cache-Control` header and `versionId` and `versionTime` as query parameters.
+    ///
+    /// Returns the HTTP status and body of the response, for the caller to interpret.
+    pub async fn request(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> Result<(reqwest::StatusCode, Vec<u8>), Error> {
+        let url = format!("{}{}", self.endpoint, did);
+        let client = reqwest::Client::new();
+        let mut request = client.get(&url).header("Accept", TYPE_DID_RESOLUTION);
+        if input_metadata.no_cache == Some(true) {
+            request = request.header("Cache-Control", "no-cache");
+        }
+        if let Some(version_id) = &input_metadata.version_id {
+            request = request.query(&[("versionId", version_id)]);
+        }
+        if let Some(version_time) = &input_metadata.version_time {
+            let version_time = version_time.to_rfc3339_opts(SecondsFormat::Secs, true);
+            request = request.query(&[("versionTime", version_time)]);
+        }
+        let resp = request
+            .send()
+            .await
+            .map_err(|err| Error::Internal(format!("{url}: {err}")))?;
+        let status = resp.status();
+        let body = resp
+            .bytes()
+            .await
+            .map_err(|err| Error::Internal(format!("{url}: {err}")))?;
+        Ok((status, body.to_vec()))
+    }
+}
+
+#[async_trait]
+impl DIDResolver for HTTPDIDResolver {
+    /// Resolve a DID over HTTP(S), using the [DID Resolution HTTP(S) Binding](https://w3c-ccg.github.io/did-resolution/#bindings-https).
+    async fn resolve(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (
+        ResolutionMetadata,
+        Option<Document>,
+        Option<DocumentMetadata>,
+    ) {
+        let (status, body) = match self.request(did, input_metadata).await {
+            Ok(response) => response,
+            Err(err) => return (ResolutionMetadata::from_error(err.code()), None, None),
+        };
+        let result: ResolutionResult = match serde_json::from_slice(&body) {
+            Ok(result) => result,
+            Err(_) if status == reqwest::StatusCode::NOT_FOUND => {
+                return (
+                    ResolutionMetadata::from_error(ErrorCode::NotFound),
+                    None,
+                    None,
+                )
+            }
+            Err(_) => {
+                return (
+                    ResolutionMetadata::from_error(ErrorCode::InternalError),
+                    None,
+                    None,
+                )
+            }
+        };
+        let (mut res_meta, doc_opt, doc_meta_opt) = result.into_parts();
+        if res_meta.error.is_none() && status == reqwest::StatusCode::NOT_FOUND {
+            res_meta.error = Some(ErrorCode::NotFound);
+        }
+        // A resolver that does not support versioned resolution may ignore the version
+        // parameters and return the latest version, so the version must be confirmed by the
+        // document metadata.
+        if res_meta.error.is_none() && !input_metadata.is_version_confirmed(doc_meta_opt.as_ref()) {
+            return (
+                ResolutionMetadata::from_error(ErrorCode::NotFound),
+                None,
+                None,
+            );
+        }
+        (res_meta, doc_opt, doc_meta_opt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn metadata_value_round_trip() {
+        let value = json!({
+            "string": "a",
+            "map": {"list": [1, 2.5, -3], "null": null},
+            "boolean": false,
+        });
+        let metadata = Metadata::from(value.clone());
+        match &metadata {
+            Metadata::Map(map) => match &map["map"] {
+                Metadata::Map(map) => {
+                    assert!(matches!(&map["list"], Metadata::List(list) if list.len() == 3));
+                    assert!(matches!(map["null"], Metadata::Null));
+                }
+                other => panic!("{:?}", other),
+            },
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(Value::from(metadata.clone()), value);
+        assert_eq!(serde_json::to_value(&metadata).unwrap(), value);
+    }
 }
@@ -1,9 +1,221 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
+/// Error code for DID Resolution and DID Registration
+///
+/// Serialized as the `error` property of [DID Resolution
+/// Metadata](https://www.w3.org/TR/did-core/#did-resolution-metadata).
+///
+/// ### References
+/// - [DID Specification Registries §DID Resolution Metadata](https://www.w3.org/TR/did-spec-registries/#did-resolution-metadata)
+/// - [DID Resolution §Errors](https://w3c-ccg.github.io/did-resolution/#errors)
+/// - [DID Registration](https://identity.foundation/did-registration/)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// [`invalidDid`](https://www.w3.org/TR/did-spec-registries/#invaliddid)
+    InvalidDid,
+    /// [`notFound`](https://www.w3.org/TR/did-spec-registries/#notfound)
+    NotFound,
+    /// [`methodNotSupported`](https://w3c-ccg.github.io/did-resolution/#errors)
+    MethodNotSupported,
+    /// [`representationNotSupported`](https://www.w3.org/TR/did-spec-registries/#representationnotsupported)
+    RepresentationNotSupported,
+    /// [`invalidOptions`](https://w3c-ccg.github.io/did-resolution/#errors)
+    InvalidOptions,
+    /// [`unauthorized`](https://identity.foundation/did-registration/)
+    Unauthorized,
+    /// [`internalError`](https://w3c-ccg.github.io/did-resolution/#errors)
+    InternalError,
+    /// Error code not defined by the specifications
+    Other(String),
+}
+
+impl ErrorCode {
+    /// The error code as it appears in metadata
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::InvalidDid => "invalidDid",
+            Self::NotFound => "notFound",
+            Self::MethodNotSupported => "methodNotSupported",
+            Self::RepresentationNotSupported => "representationNotSupported",
+            Self::InvalidOptions => "invalidOptions",
+            Self::Unauthorized => "unauthorized",
+            Self::InternalError => "internalError",
+            Self::Other(code) => code,
+        }
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "invalidDid" => Self::InvalidDid,
+            "notFound" => Self::NotFound,
+            "methodNotSupported" => Self::MethodNotSupported,
+            "representationNotSupported" => Self::RepresentationNotSupported,
+            "invalidOptions" => Self::InvalidOptions,
+            "unauthorized" => Self::Unauthorized,
+            "internalError" => Self::InternalError,
+            other => Self::Other(other.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Ok(code.parse().unwrap_or_else(|never| match never {}))
+    }
+}
+
 /// Error type for `ssi`.
 #[derive(Error, Debug)]
 pub enum Error {
     /// Key mismatch
     #[error("Key mismatch")]
     KeyMismatch,
+    /// Not a valid DID or DID URL
+    #[error("Invalid DID: {0}")]
+    InvalidDid(String),
+    /// DID or DID document not found
+    #[error("DID not found: {0}")]
+    NotFound(String),
+    /// DID method not supported
+    #[error("DID method not supported: {0}")]
+    MethodNotSupported(String),
+    /// Requested DID document representation not supported
+    #[error("Representation not supported: {0}")]
+    RepresentationNotSupported(String),
+    /// Invalid options or input metadata
+    #[error("Invalid options: {0}")]
+    InvalidOptions(String),
+    /// Operation not authorized
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    /// Unexpected error while processing a request
+    #[error("Internal error: {0}")]
+    Internal(String),
+    /// Error with an error code not defined by the specifications
+    #[error("{code}: {message}")]
+    Other { code: String, message: String },
+}
+
+impl Error {
+    /// Construct an error from an error code, e.g. from DID resolution metadata.
+    pub fn from_code(code: ErrorCode, message: String) -> Self {
+        match code {
+            ErrorCode::InvalidDid => Self::InvalidDid(message),
+            ErrorCode::NotFound => Self::NotFound(message),
+            ErrorCode::MethodNotSupported => Self::MethodNotSupported(message),
+            ErrorCode::RepresentationNotSupported => Self::RepresentationNotSupported(message),
+            ErrorCode::InvalidOptions => Self::InvalidOptions(message),
+            ErrorCode::Unauthorized => Self::Unauthorized(message),
+            ErrorCode::InternalError => Self::Internal(message),
+            ErrorCode::Other(code) => Self::Other { code, message },
+        }
+    }
+
+    /// Error code for the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::KeyMismatch => ErrorCode::Unauthorized,
+            Self::InvalidDid(_) => ErrorCode::InvalidDid,
+            Self::NotFound(_) => ErrorCode::NotFound,
+            Self::MethodNotSupported(_) => ErrorCode::MethodNotSupported,
+            Self::RepresentationNotSupported(_) => ErrorCode::RepresentationNotSupported,
+            Self::InvalidOptions(_) => ErrorCode::InvalidOptions,
+            Self::Unauthorized(_) => ErrorCode::Unauthorized,
+            Self::Internal(_) => ErrorCode::InternalError,
+            Self::Other { code, .. } => ErrorCode::Other(code.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did_resolve::ResolutionMetadata;
+    use serde_json::json;
+
+    const CODES: [(ErrorCode, &str); 7] = [
+        (ErrorCode::InvalidDid, "invalidDid"),
+        (ErrorCode::NotFound, "notFound"),
+        (ErrorCode::MethodNotSupported, "methodNotSupported"),
+        (
+            ErrorCode::RepresentationNotSupported,
+            "representationNotSupported",
+        ),
+        (ErrorCode::InvalidOptions, "invalidOptions"),
+        (ErrorCode::Unauthorized, "unauthorized"),
+        (ErrorCode::InternalError, "internalError"),
+    ];
+
+    #[test]
+    fn error_code_strings() {
+        for (code, name) in CODES {
+            assert_eq!(code.as_str(), name);
+            assert_eq!(code.to_string(), name);
+            assert_eq!(name.parse::<ErrorCode>().unwrap(), code);
+        }
+        assert_eq!(
+            "notAnError".parse::<ErrorCode>().unwrap(),
+            ErrorCode::Other("notAnError".to_string())
+        );
+        assert_eq!(
+            ErrorCode::Other("notAnError".to_string()).as_str(),
+            "notAnError"
+        );
+    }
+
+    #[test]
+    fn serialize_resolution_metadata_error() {
+        let res_meta = ResolutionMetadata::from_error(ErrorCode::RepresentationNotSupported);
+        assert_eq!(
+            serde_json::to_value(&res_meta).unwrap(),
+            json!({"error": "representationNotSupported"})
+        );
+        let res_meta: ResolutionMetadata =
+            serde_json::from_value(json!({"error": "notFound"})).unwrap();
+        assert_eq!(res_meta.error, Some(ErrorCode::NotFound));
+        let res_meta: ResolutionMetadata =
+            serde_json::from_value(json!({"error": "customError"})).unwrap();
+        assert_eq!(
+            res_meta.error,
+            Some(ErrorCode::Other("customError".to_string()))
+        );
+        assert_eq!(
+            serde_json::to_value(&res_meta).unwrap(),
+            json!({"error": "customError"})
+        );
+    }
+
+    #[test]
+    fn from_code_round_trip() {
+        let codes = CODES
+            .iter()
+            .map(|(code, _)| code.clone())
+            .chain([ErrorCode::Other("customError".to_string())]);
+        for code in codes {
+            let err = Error::from_code(code.clone(), "message".to_string());
+            assert_eq!(err.code(), code);
+            assert!(err.to_string().contains("message"), "{}", err);
+        }
+        assert_eq!(Error::KeyMismatch.code(), ErrorCode::Unauthorized);
+    }
 }
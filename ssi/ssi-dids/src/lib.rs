@@ -5,6 +5,7 @@
 //! [did-core]: https://www.w3.org/TR/did-core/
 //!
 
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::{BTreeMap as Map, HashMap};
@@ -12,15 +13,30 @@ use thiserror::Error;
 
 use ssi_jwk::JWK;
 
+use crate::did_resolve::{
+    DIDResolver, DocumentMetadata, ResolutionInputMetadata, ResolutionMetadata,
+};
+use crate::error::{Error, ErrorCode};
+
+pub mod bundle;
+pub mod cache;
+pub mod controller;
+pub mod did_configuration;
 pub mod did_resolve;
 pub mod error;
+pub mod representation;
+pub mod service;
+pub mod validate;
+
+/// URI [required](https://www.w3.org/TR/did-core/#production-0) as the first value of the `@context` property for a DID Document in JSON-LD representation.
+pub const DEFAULT_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
 
 /// A [verification relationship](https://w3c.github.io/did-core/#dfn-verification-relationship).
 ///
 /// The relationship between a [verification method][VerificationMethod] and a DID
 /// Subject (as described by a [DID Document][Document]) is considered analogous to a [proof
 /// purpose](crate::vc::ProofPurpose).
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub enum VerificationRelationship {
     Authentication,
@@ -36,7 +52,7 @@ pub enum VerificationRelationship {
 ///
 /// "The value of the serviceEndpoint property MUST be a string \[URI], a map, or a set composed of one or
 /// more strings \[URIs] and/or maps."
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[serde(untagged)]
 pub enum ServiceEndpoint {
@@ -44,6 +60,294 @@ pub enum ServiceEndpoint {
     Map(Value),
 }
 
+/// A value that may be given either as a single item or as a set of items.
+///
+/// Used for DID document properties such as `controller` and `@context`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    // Try `Many` first, so that arrays are not taken as a single value of a permissive type such
+    // as `ServiceEndpoint::Map`.
+    Many(Vec<T>),
+    One(T),
+}
+
+impl<T> OneOrMany<T> {
+    /// Get the values as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            Self::One(value) => std::slice::from_ref(value),
+            Self::Many(values) => values,
+        }
+    }
+
+    /// Iterate over the values.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Convert into a list of values.
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::One(value) => vec![value],
+            Self::Many(values) => values,
+        }
+    }
+}
+
+/// A value of the [`@context`](https://www.w3.org/TR/did-core/#dfn-context) property.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Context {
+    URI(String),
+    Object(Map<String, Value>),
+}
+
+/// A [DID document](https://www.w3.org/TR/did-core/#dfn-did-documents)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Document {
+    /// [`@context`](https://www.w3.org/TR/did-core/#dfn-context) property
+    ///
+    /// Only present in the JSON-LD representation.
+    #[serde(rename = "@context")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<OneOrMany<Context>>,
+
+    /// [DID Subject](https://www.w3.org/TR/did-core/#did-subject)
+    pub id: String,
+
+    /// [`alsoKnownAs`](https://www.w3.org/TR/did-core/#also-known-as)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub also_known_as: Option<Vec<String>>,
+
+    /// [DID Controller](https://www.w3.org/TR/did-core/#did-controller)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub controller: Option<OneOrMany<String>>,
+
+    /// [Verification methods](https://www.w3.org/TR/did-core/#verification-methods)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification_method: Option<Vec<VerificationMethod>>,
+
+    /// [`authentication`](https://www.w3.org/TR/did-core/#authentication) verification relationship
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authentication: Option<Vec<VerificationMethod>>,
+
+    /// [`assertionMethod`](https://www.w3.org/TR/did-core/#assertion) verification relationship
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assertion_method: Option<Vec<VerificationMethod>>,
+
+    /// [`keyAgreement`](https://www.w3.org/TR/did-core/#key-agreement) verification relationship
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_agreement: Option<Vec<VerificationMethod>>,
+
+    /// [`capabilityInvocation`](https://www.w3.org/TR/did-core/#capability-invocation) verification relationship
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capability_invocation: Option<Vec<VerificationMethod>>,
+
+    /// [`capabilityDelegation`](https://www.w3.org/TR/did-core/#capability-delegation) verification relationship
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capability_delegation: Option<Vec<VerificationMethod>>,
+
+    /// [Services](https://www.w3.org/TR/did-core/#services)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<Vec<Service>>,
+
+    /// Additional properties
+    #[serde(flatten)]
+    pub property_set: Option<Map<String, Value>>,
+}
+
+/// A [verification method](https://www.w3.org/TR/did-core/#verification-methods), either
+/// embedded or referenced by DID URL.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum VerificationMethod {
+    /// Absolute or relative DID URL referencing a verification method
+    DIDURL(String),
+    /// Embedded verification method
+    Map(VerificationMethodMap),
+}
+
+/// A [verification method map](https://www.w3.org/TR/did-core/#verification-methods)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethodMap {
+    /// Verification method id (DID URL)
+    pub id: String,
+
+    /// Verification method type
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// Controller of the verification method (DID)
+    pub controller: String,
+
+    /// [`publicKeyJwk`](https://www.w3.org/TR/did-core/#dfn-publickeyjwk)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key_jwk: Option<JWK>,
+
+    /// [`publicKeyMultibase`](https://www.w3.org/TR/did-core/#dfn-publickeymultibase)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key_multibase: Option<String>,
+
+    /// Additional properties
+    #[serde(flatten)]
+    pub property_set: Option<Map<String, Value>>,
+}
+
+/// A [service](https://www.w3.org/TR/did-core/#services) map in a DID document.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Service {
+    /// Service id (URI)
+    pub id: String,
+
+    /// Service type(s)
+    #[serde(rename = "type")]
+    pub type_: OneOrMany<String>,
+
+    /// [Service endpoint(s)](https://www.w3.org/TR/did-core/#dfn-serviceendpoint)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_endpoint: Option<OneOrMany<ServiceEndpoint>>,
+
+    /// Additional properties
+    #[serde(flatten)]
+    pub property_set: Option<Map<String, Value>>,
+}
+
+impl Document {
+    /// Construct a new DID document with the given id and the [default context][DEFAULT_CONTEXT].
+    pub fn new(id: &str) -> Self {
+        Self {
+            context: Some(OneOrMany::One(Context::URI(DEFAULT_CONTEXT.to_string()))),
+            id: id.to_string(),
+            also_known_as: None,
+            controller: None,
+            verification_method: None,
+            authentication: None,
+            assertion_method: None,
+            key_agreement: None,
+            capability_invocation: None,
+            capability_delegation: None,
+            service: None,
+            property_set: None,
+        }
+    }
+
+    /// Get the verification methods listed for a verification relationship, as they appear in
+    /// the document (embedded or by reference).
+    pub fn get_relationship(&self, rel: VerificationRelationship) -> &[VerificationMethod] {
+        let vms = match rel {
+            VerificationRelationship::Authentication => &self.authentication,
+            VerificationRelationship::AssertionMethod => &self.assertion_method,
+            VerificationRelationship::KeyAgreement => &self.key_agreement,
+            VerificationRelationship::CapabilityInvocation => &self.capability_invocation,
+            VerificationRelationship::CapabilityDelegation => &self.capability_delegation,
+        };
+        vms.as_deref().unwrap_or_default()
+    }
+
+    /// Get a verification method embedded in this document, by absolute or relative id.
+    pub fn find_verification_method(&self, id: &str) -> Option<&VerificationMethodMap> {
+        let id = absolute_did_url(&self.id, id);
+        let relationships = VerificationRelationship::ALL
+            .iter()
+            .flat_map(|rel| self.get_relationship(*rel));
+        self.verification_method
+            .iter()
+            .flatten()
+            .chain(relationships)
+            .find_map(|vm| match vm {
+                VerificationMethod::Map(vmm) if vmm.get_id(&self.id) == id => Some(vmm),
+                _ => None,
+            })
+    }
+
+    /// Get the verification methods authorized for a verification relationship.
+    ///
+    /// References are expanded to the verification methods embedded in this document.
+    /// References to verification methods in other DID documents are not included.
+    pub fn verification_methods_for(
+        &self,
+        rel: VerificationRelationship,
+    ) -> Vec<&VerificationMethodMap> {
+        self.get_relationship(rel)
+            .iter()
+            .filter_map(|vm| match vm {
+                VerificationMethod::Map(vmm) => Some(vmm),
+                VerificationMethod::DIDURL(id) => self.find_verification_method(id),
+            })
+            .collect()
+    }
+
+    /// Get the verification methods authorized for a verification relationship that have the
+    /// public key of the given JWK.
+    pub fn verification_methods_for_jwk(
+        &self,
+        rel: VerificationRelationship,
+        jwk: &JWK,
+    ) -> Vec<&VerificationMethodMap> {
+        self.verification_methods_for(rel)
+            .into_iter()
+            .filter(|vmm| vmm.match_jwk(jwk))
+            .collect()
+    }
+
+    /// Select the verification method to use for signing with a local key, for a verification
+    /// relationship.
+    ///
+    /// Returns the first authorized verification method with the key's public key, in document
+    /// order.
+    pub fn select_verification_method(
+        &self,
+        rel: VerificationRelationship,
+        jwk: &JWK,
+    ) -> Result<&VerificationMethodMap, Error> {
+        self.verification_methods_for_jwk(rel, jwk)
+            .into_iter()
+            .next()
+            .ok_or(Error::KeyMismatch)
+    }
+}
+
+impl VerificationMethodMap {
+    /// Get the absolute id of the verification method, resolving a relative id against the DID.
+    pub fn get_id(&self, did: &str) -> String {
+        absolute_did_url(did, &self.id)
+    }
+
+    /// Whether the verification method's `publicKeyJwk` has the public key of the given JWK.
+    pub fn match_jwk(&self, jwk: &JWK) -> bool {
+        match &self.public_key_jwk {
+            Some(public_key_jwk) => public_key_jwk.equals_public(jwk),
+            None => false,
+        }
+    }
+}
+
+impl VerificationRelationship {
+    /// All verification relationships defined in DID Core
+    pub const ALL: [Self; 5] = [
+        Self::Authentication,
+        Self::AssertionMethod,
+        Self::KeyAgreement,
+        Self::CapabilityInvocation,
+        Self::CapabilityDelegation,
+    ];
+
+    /// Name of the DID document property for the verification relationship
+    pub fn property_name(&self) -> &'static str {
+        match self {
+            Self::Authentication => "authentication",
+            Self::AssertionMethod => "assertionMethod",
+            Self::KeyAgreement => "keyAgreement",
+            Self::CapabilityInvocation => "capabilityInvocation",
+            Self::CapabilityDelegation => "capabilityDelegation",
+        }
+    }
+}
+
 /// DID Create Operation
 ///
 /// <https://identity.foundation/did-registration/#create>
@@ -54,12 +358,94 @@ pub struct DIDCreate {
     pub options: Map<String, Value>,
 }
 
+/// DID Update Operation
+///
+/// <https://identity.foundation/did-registration/#update>
+pub struct DIDUpdate {
+    /// DID to update
+    pub did: String,
+    /// Current update key, authorizing the operation
+    pub update_key: Option<JWK>,
+    /// New update key, for the next update operation
+    pub new_update_key: Option<JWK>,
+    /// Changes to make to the DID document
+    pub operation: DIDDocumentOperation,
+    pub options: Map<String, Value>,
+}
+
+/// DID Recover Operation
+///
+/// For DID methods with a separate recovery key, such as Sidetree. Recovery replaces the DID
+/// document and keys, and may follow a compromise of the update key.
+///
+/// <https://identity.foundation/did-registration/>
+pub struct DIDRecover {
+    /// DID to recover
+    pub did: String,
+    /// Current recovery key, authorizing the operation
+    pub recovery_key: Option<JWK>,
+    /// New update key, for the next update operation
+    pub new_update_key: Option<JWK>,
+    /// New recovery key, for the next recover or deactivate operation
+    pub new_recovery_key: Option<JWK>,
+    /// New key for the default verification method
+    pub new_verification_key: Option<JWK>,
+    pub options: Map<String, Value>,
+}
+
+/// DID Deactivate Operation
+///
+/// <https://identity.foundation/did-registration/#deactivate>
+pub struct DIDDeactivate {
+    /// DID to deactivate
+    pub did: String,
+    /// Key authorizing the operation, e.g. the recovery key
+    pub key: Option<JWK>,
+    pub options: Map<String, Value>,
+}
+
+/// DID Document Operation
+///
+/// Change to a DID document requested in a [DID Update](DIDUpdate) operation.
+///
+/// `setDidDocument`, `addToDidDocument` and `removeFromDidDocument` are defined in [DID
+/// Registration](https://identity.foundation/did-registration/#diddocumentoperation). The other
+/// operations are extensions, for DID methods that change verification methods and services
+/// individually, such as Sidetree.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "didDocumentOperation", content = "didDocument")]
+#[serde(rename_all = "camelCase")]
+pub enum DIDDocumentOperation {
+    /// Set the contents of the DID document
+    SetDidDocument(Document),
+    /// Add properties to the DID document
+    AddToDidDocument(Map<String, Value>),
+    /// Remove properties from the DID document
+    RemoveFromDidDocument(Vec<String>),
+    /// Extension: add or replace a verification method, with the given verification
+    /// relationships
+    SetVerificationMethod {
+        vmm: VerificationMethodMap,
+        purposes: Vec<VerificationRelationship>,
+    },
+    /// Extension: add or replace a service
+    SetService(Service),
+    /// Extension: remove a verification method, by id
+    RemoveVerificationMethod(String),
+    /// Extension: remove a service, by id
+    RemoveService(String),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DIDMethodTransaction {
     /// DID method name
     pub did_method: String,
 
+    /// DID that the transaction is for, e.g. the DID created by a create transaction, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub did: Option<String>,
+
     /// Method-specific transaction data
     // #[serde(flatten)]
     pub value: Value,
@@ -76,10 +462,29 @@ pub enum DIDMethodError {
         option: String,
     },
     #[error(transparent)]
+    Error(#[from] Error),
+    #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
+impl DIDMethodError {
+    /// [Error code](ErrorCode) for the error, for reporting in DID Resolution or DID
+    /// Registration metadata
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::NotImplemented(_) => ErrorCode::MethodNotSupported,
+            Self::OptionNotSupported { .. } => ErrorCode::InvalidOptions,
+            Self::Error(err) => err.code(),
+            Self::Other(err) => match err.downcast_ref::<Error>() {
+                Some(err) => err.code(),
+                None => ErrorCode::InternalError,
+            },
+        }
+    }
+}
+
 /// An implementation of a [DID method](https://www.w3.org/TR/did-core/#dfn-did-methods).
+#[async_trait]
 pub trait DIDMethod: Sync + Send {
     /// Get the DID method's name.
     ///
@@ -90,12 +495,92 @@ pub trait DIDMethod: Sync + Send {
     fn create(&self, _create: DIDCreate) -> Result<DIDMethodTransaction, DIDMethodError> {
         Err(DIDMethodError::NotImplemented("Create operation"))
     }
+
+    /// Update a DID
+    fn update(&self, _update: DIDUpdate) -> Result<DIDMethodTransaction, DIDMethodError> {
+        Err(DIDMethodError::NotImplemented("Update operation"))
+    }
+
+    /// Recover a DID
+    fn recover(&self, _recover: DIDRecover) -> Result<DIDMethodTransaction, DIDMethodError> {
+        Err(DIDMethodError::NotImplemented("Recover operation"))
+    }
+
+    /// Deactivate a DID
+    fn deactivate(
+        &self,
+        _deactivate: DIDDeactivate,
+    ) -> Result<DIDMethodTransaction, DIDMethodError> {
+        Err(DIDMethodError::NotImplemented("Deactivate operation"))
+    }
+
+    /// Get the DID that a DID method transaction is for, e.g. the DID created by a create
+    /// transaction.
+    fn did_from_transaction(&self, tx: &DIDMethodTransaction) -> Result<String, DIDMethodError> {
+        tx.did
+            .clone()
+            .ok_or(DIDMethodError::NotImplemented("DID from transaction"))
+    }
+
+    /// Submit a DID transaction, e.g. to a DID method's network or registry.
+    ///
+    /// Returns the method-specific response.
+    async fn submit_transaction(&self, _tx: DIDMethodTransaction) -> Result<Value, DIDMethodError> {
+        Err(DIDMethodError::NotImplemented("Transaction submission"))
+    }
+
+    /// Get the DID method's resolver, if it implements resolution.
+    fn to_resolver(&self) -> Option<&dyn DIDResolver> {
+        None
+    }
+}
+
+/// Get the [method name](https://www.w3.org/TR/did-core/#did-syntax) of a DID.
+///
+/// Returns `None` if the DID does not have the form `did:<method-name>:<method-specific-id>`.
+pub fn did_method_name(did: &str) -> Option<&str> {
+    let mut parts = did.splitn(3, ':');
+    if parts.next()? != "did" {
+        return None;
+    }
+    let method_name = parts.next()?;
+    let method_specific_id = parts.next()?;
+    if method_name.is_empty()
+        || !method_name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        || method_specific_id.is_empty()
+    {
+        return None;
+    }
+    Some(method_name)
+}
+
+/// Resolve a DID URL reference, such as a verification method id, against a base DID.
+///
+/// Relative references (e.g. `#key-1`) are [resolved][rdu] against the base DID; other values
+/// are returned unchanged.
+///
+/// [rdu]: https://www.w3.org/TR/did-core/#relative-did-urls
+pub fn absolute_did_url(base_did: &str, reference: &str) -> String {
+    if reference.starts_with('#') || reference.starts_with('?') || reference.starts_with('/') {
+        format!("{base_did}{reference}")
+    } else {
+        reference.to_string()
+    }
 }
 
 /// A collection of DID methods
+///
+/// Also a [DIDResolver], dispatching resolution to the DID method named in the DID, or to a
+/// [fallback resolver][DIDMethods::fallback] if the method is not in the set or does not
+/// implement resolution.
 #[derive(Default)]
 pub struct DIDMethods<'a> {
     pub methods: HashMap<&'a str, Box<dyn DIDMethod>>,
+    /// Resolver for DIDs that no DID method in the set can resolve, e.g. a
+    /// [Universal Resolver](crate::did_resolve::HTTPDIDResolver)
+    pub fallback: Option<Box<dyn DIDResolver + Send>>,
 }
 
 impl<'a> DIDMethods<'a> {
@@ -106,7 +591,392 @@ impl<'a> DIDMethods<'a> {
     }
 
     /// Get a DID method from the set.
-    pub fn get(&self, method_name: &str) -> Option<&Box<dyn DIDMethod>> {
-        self.methods.get(method_name)
+    pub fn get(&self, method_name: &str) -> Option<&dyn DIDMethod> {
+        self.methods.get(method_name).map(|method| method.as_ref())
+    }
+
+    /// Set the [fallback resolver][DIDMethods::fallback]. Returns the previous one, if any.
+    pub fn set_fallback(
+        &mut self,
+        resolver: Box<dyn DIDResolver + Send>,
+    ) -> Option<Box<dyn DIDResolver + Send>> {
+        self.fallback.replace(resolver)
+    }
+
+    /// Get the DID method for a DID.
+    pub fn get_method(&self, did: &str) -> Result<&dyn DIDMethod, Error> {
+        let method_name = did_method_name(did).ok_or_else(|| Error::InvalidDid(did.to_string()))?;
+        self.get(method_name)
+            .ok_or_else(|| Error::MethodNotSupported(method_name.to_string()))
+    }
+}
+
+#[async_trait]
+impl<'a> DIDResolver for DIDMethods<'a> {
+    async fn resolve(
+        &self,
+        did: &str,
+        input_metadata: &ResolutionInputMetadata,
+    ) -> (
+        ResolutionMetadata,
+        Option<Document>,
+        Option<DocumentMetadata>,
+    ) {
+        let resolver = match self.get_method(did) {
+            Ok(method) => method.to_resolver(),
+            Err(err @ Error::InvalidDid(_)) => {
+                return (ResolutionMetadata::from_error(err.code()), None, None)
+            }
+            Err(_) => None,
+        };
+        let resolver = match resolver.or(self.fallback.as_deref().map(|r| r as &dyn DIDResolver)) {
+            Some(resolver) => resolver,
+            None => {
+                return (
+                    ResolutionMetadata::from_error(ErrorCode::MethodNotSupported),
+                    None,
+                    None,
+                )
+            }
+        };
+        resolver.resolve(did, input_metadata).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Resolver returning a document with its name as the DID, to tell resolvers apart
+    struct NamedResolver(&'static str);
+
+    #[async_trait]
+    impl DIDResolver for NamedResolver {
+        async fn resolve(
+            &self,
+            _did: &str,
+            _input_metadata: &ResolutionInputMetadata,
+        ) -> (
+            ResolutionMetadata,
+            Option<Document>,
+            Option<DocumentMetadata>,
+        ) {
+            (
+                ResolutionMetadata::default(),
+                Some(Document::new(self.0)),
+                None,
+            )
+        }
+    }
+
+    struct ExampleMethod {
+        name: &'static str,
+        resolver: Option<NamedResolver>,
+    }
+
+    impl DIDMethod for ExampleMethod {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn to_resolver(&self) -> Option<&dyn DIDResolver> {
+            self.resolver
+                .as_ref()
+                .map(|resolver| resolver as &dyn DIDResolver)
+        }
+    }
+
+    fn methods() -> DIDMethods<'static> {
+        let mut methods = DIDMethods::default();
+        methods.insert(Box::new(ExampleMethod {
+            name: "example",
+            resolver: Some(NamedResolver("example")),
+        }));
+        methods.insert(Box::new(ExampleMethod {
+            name: "noresolver",
+            resolver: None,
+        }));
+        methods
+    }
+
+    async fn resolve(methods: &DIDMethods<'_>, did: &str) -> Result<String, ErrorCode> {
+        let (res_meta, doc_opt, _) = methods
+            .resolve(did, &ResolutionInputMetadata::default())
+            .await;
+        match res_meta.error {
+            Some(error) => Err(error),
+            None => Ok(doc_opt.unwrap().id),
+        }
+    }
+
+    #[test]
+    fn operations_not_implemented_by_default() {
+        let method = ExampleMethod {
+            name: "example",
+            resolver: None,
+        };
+        let not_implemented = |result: Result<DIDMethodTransaction, DIDMethodError>| {
+            matches!(result, Err(DIDMethodError::NotImplemented(_)))
+        };
+        assert!(not_implemented(method.create(DIDCreate {
+            update_key: None,
+            recovery_key: None,
+            verification_key: None,
+            options: Map::new(),
+        })));
+        assert!(not_implemented(method.update(DIDUpdate {
+            did: "did:example:123".to_string(),
+            update_key: None,
+            new_update_key: None,
+            operation: DIDDocumentOperation::RemoveService("#service".to_string()),
+            options: Map::new(),
+        })));
+        assert!(not_implemented(method.recover(DIDRecover {
+            did: "did:example:123".to_string(),
+            recovery_key: None,
+            new_update_key: None,
+            new_recovery_key: None,
+            new_verification_key: None,
+            options: Map::new(),
+        })));
+        assert!(not_implemented(method.deactivate(DIDDeactivate {
+            did: "did:example:123".to_string(),
+            key: None,
+            options: Map::new(),
+        })));
+    }
+
+    #[tokio::test]
+    async fn submit_transaction_not_implemented_by_default() {
+        let method = ExampleMethod {
+            name: "example",
+            resolver: None,
+        };
+        let tx = DIDMethodTransaction {
+            did_method: "example".to_string(),
+            did: None,
+            value: Value::Null,
+        };
+        assert!(matches!(
+            method.submit_transaction(tx).await,
+            Err(DIDMethodError::NotImplemented(_))
+        ));
+    }
+
+    #[test]
+    fn did_document_operation_serialization() {
+        let operation: DIDDocumentOperation = serde_json::from_value(serde_json::json!({
+            "didDocumentOperation": "removeFromDidDocument",
+            "didDocument": ["service"],
+        }))
+        .unwrap();
+        assert!(matches!(
+            operation,
+            DIDDocumentOperation::RemoveFromDidDocument(properties) if properties == ["service"]
+        ));
+        let operation = DIDDocumentOperation::RemoveService("#service".to_string());
+        assert_eq!(
+            serde_json::to_value(&operation).unwrap(),
+            serde_json::json!({
+                "didDocumentOperation": "removeService",
+                "didDocument": "#service",
+            })
+        );
+    }
+
+    #[test]
+    fn parse_did_method_name() {
+        assert_eq!(did_method_name("did:example:123"), Some("example"));
+        assert_eq!(did_method_name("did:web:example.com:user"), Some("web"));
+        assert_eq!(did_method_name("did:example:"), None);
+        assert_eq!(did_method_name("did:Example:123"), None);
+        assert_eq!(did_method_name("did::123"), None);
+        assert_eq!(did_method_name("urn:example:123"), None);
+    }
+
+    #[tokio::test]
+    async fn resolve_dispatches_by_method_name() {
+        let methods = methods();
+        assert_eq!(
+            resolve(&methods, "did:example:123").await.unwrap(),
+            "example"
+        );
+        assert!(methods.get_method("did:example:123").is_ok());
+    }
+
+    #[tokio::test]
+    async fn resolve_without_matching_method() {
+        let methods = methods();
+        assert_eq!(
+            resolve(&methods, "did:other:123").await.unwrap_err(),
+            ErrorCode::MethodNotSupported
+        );
+        assert_eq!(
+            resolve(&methods, "did:noresolver:123").await.unwrap_err(),
+            ErrorCode::MethodNotSupported
+        );
+        assert_eq!(
+            resolve(&methods, "not-a-did").await.unwrap_err(),
+            ErrorCode::InvalidDid
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_uses_fallback() {
+        let mut methods = methods();
+        assert!(methods
+            .set_fallback(Box::new(NamedResolver("fallback")))
+            .is_none());
+        assert_eq!(
+            resolve(&methods, "did:other:123").await.unwrap(),
+            "fallback"
+        );
+        assert_eq!(
+            resolve(&methods, "did:noresolver:123").await.unwrap(),
+            "fallback"
+        );
+        // Methods with a resolver are not sent to the fallback.
+        assert_eq!(
+            resolve(&methods, "did:example:123").await.unwrap(),
+            "example"
+        );
+        // Invalid DIDs are not sent to the fallback either.
+        assert_eq!(
+            resolve(&methods, "not-a-did").await.unwrap_err(),
+            ErrorCode::InvalidDid
+        );
+    }
+
+    const DID: &str = "did:example:123";
+
+    fn key_1() -> JWK {
+        serde_json::from_value(serde_json::json!({
+            "kty": "EC",
+            "crv": "secp256k1",
+            "x": "WfY7Px6AgH6x-_dgAoRbg8weYRJA36ON-gQiFnETrqw",
+            "y": "IzFx3BUGztK0cyDStiunXbrZYYTtKbOUzx16SUK0sAY"
+        }))
+        .unwrap()
+    }
+
+    fn key_2() -> JWK {
+        serde_json::from_value(serde_json::json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "x": "f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU",
+            "y": "x_FEzRjj6Ho9ZwG7ZVk_PXwW5tSgCYv6IHUKLtEkqFg"
+        }))
+        .unwrap()
+    }
+
+    /// Document with key 1 in `verificationMethod`, referenced relatively and absolutely, and
+    /// key 2 embedded in `assertionMethod`
+    fn relationships_document() -> Document {
+        serde_json::from_value(serde_json::json!({
+            "id": DID,
+            "verificationMethod": [{
+                "id": "#key-1",
+                "type": "EcdsaSecp256k1VerificationKey2019",
+                "controller": DID,
+                "publicKeyJwk": key_1()
+            }],
+            "authentication": ["#key-1", "did:example:other#key-1"],
+            "assertionMethod": [
+                "did:example:123#key-1",
+                {
+                    "id": "did:example:123#key-2",
+                    "type": "JsonWebKey2020",
+                    "controller": DID,
+                    "publicKeyJwk": key_2()
+                },
+            ],
+            "capabilityInvocation": ["#key-2"],
+            "keyAgreement": ["#missing"]
+        }))
+        .unwrap()
+    }
+
+    fn ids(vms: Vec<&VerificationMethodMap>) -> Vec<String> {
+        vms.into_iter().map(|vmm| vmm.get_id(DID)).collect()
+    }
+
+    #[test]
+    fn verification_methods_for_relationship() {
+        let doc = relationships_document();
+        // Relative references are expanded; references to other documents are left out.
+        assert_eq!(
+            ids(doc.verification_methods_for(VerificationRelationship::Authentication)),
+            ["did:example:123#key-1"]
+        );
+        assert_eq!(
+            ids(doc.verification_methods_for(VerificationRelationship::AssertionMethod)),
+            ["did:example:123#key-1", "did:example:123#key-2"]
+        );
+        // References may point at methods embedded in another relationship.
+        assert_eq!(
+            ids(doc.verification_methods_for(VerificationRelationship::CapabilityInvocation)),
+            ["did:example:123#key-2"]
+        );
+        assert!(doc
+            .verification_methods_for(VerificationRelationship::KeyAgreement)
+            .is_empty());
+        assert!(doc
+            .verification_methods_for(VerificationRelationship::CapabilityDelegation)
+            .is_empty());
+        assert_eq!(
+            doc.find_verification_method("did:example:123#key-1")
+                .map(|vmm| vmm.id.as_str()),
+            Some("#key-1")
+        );
+    }
+
+    #[test]
+    fn verification_methods_for_jwk() {
+        let doc = relationships_document();
+        assert_eq!(
+            ids(doc
+                .verification_methods_for_jwk(VerificationRelationship::AssertionMethod, &key_2())),
+            ["did:example:123#key-2"]
+        );
+        assert!(doc
+            .verification_methods_for_jwk(VerificationRelationship::Authentication, &key_2())
+            .is_empty());
+
+        // Private key parameters are ignored when matching.
+        let mut private_key: serde_json::Value = serde_json::to_value(key_1()).unwrap();
+        private_key["d"] = "cm9vdA".into();
+        let private_key: JWK = serde_json::from_value(private_key).unwrap();
+        assert_ne!(private_key, key_1());
+        assert_eq!(
+            ids(doc.verification_methods_for_jwk(
+                VerificationRelationship::Authentication,
+                &private_key
+            )),
+            ["did:example:123#key-1"]
+        );
+    }
+
+    #[test]
+    fn select_verification_method() {
+        let doc = relationships_document();
+        assert_eq!(
+            doc.select_verification_method(VerificationRelationship::AssertionMethod, &key_1())
+                .unwrap()
+                .get_id(DID),
+            "did:example:123#key-1"
+        );
+        assert_eq!(
+            doc.select_verification_method(
+                VerificationRelationship::CapabilityInvocation,
+                &key_2()
+            )
+            .unwrap()
+            .get_id(DID),
+            "did:example:123#key-2"
+        );
+        assert!(matches!(
+            doc.select_verification_method(VerificationRelationship::KeyAgreement, &key_1()),
+            Err(Error::KeyMismatch)
+        ));
     }
 }
@@ -0,0 +1,377 @@
+//! DID document [representations](https://www.w3.org/TR/did-core/#representations)
+//!
+//! Implements the production and consumption rules for converting the [Document] data model to
+//! and from a concrete representation.
+
+use thiserror::Error;
+
+use crate::{did_resolve::TYPE_DID_LD_JSON, Context, Document, OneOrMany, DEFAULT_CONTEXT};
+
+/// Media type for the [JSON representation](https://www.w3.org/TR/did-core/#json) of a DID document
+pub const TYPE_DID_JSON: &str = "application/did+json";
+
+/// Media type for the CBOR representation of a DID document
+///
+/// Registered in [DID Specification Registries §Representation-specific
+/// entries](https://www.w3.org/TR/did-spec-registries/#application-did-cbor).
+pub const TYPE_DID_CBOR: &str = "application/did+cbor";
+
+/// A concrete representation of a DID document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representation {
+    /// `application/did+json`: JSON, without `@context`
+    Json,
+    /// `application/did+ld+json`: JSON-LD, with `@context`
+    JsonLd,
+    /// `application/did+cbor`: CBOR, without `@context`
+    Cbor,
+}
+
+/// Error converting a DID document to or from a [Representation]
+#[derive(Error, Debug)]
+pub enum RepresentationError {
+    /// No supported representation matches the requested media type(s)
+    #[error("Representation not supported: {0}")]
+    NotSupported(String),
+    /// The JSON-LD representation requires `@context`
+    #[error("Missing @context in JSON-LD representation")]
+    MissingContext,
+    /// The first `@context` value in the JSON-LD representation must be the DID v1 context
+    #[error("Expected first @context value to be {DEFAULT_CONTEXT}")]
+    InvalidContext,
+    #[error("Unable to convert JSON representation")]
+    Json(#[from] serde_json::Error),
+    #[error("Unable to convert CBOR representation: {0}")]
+    Cbor(String),
+}
+
+impl Representation {
+    /// Media type of the representation
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Self::Json => TYPE_DID_JSON,
+            Self::JsonLd => TYPE_DID_LD_JSON,
+            Self::Cbor => TYPE_DID_CBOR,
+        }
+    }
+
+    /// Get the representation for a media type, ignoring media type parameters.
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        let essence = media_type.split(';').next()?.trim().to_ascii_lowercase();
+        match essence.as_str() {
+            TYPE_DID_JSON => Some(Self::Json),
+            TYPE_DID_LD_JSON => Some(Self::JsonLd),
+            TYPE_DID_CBOR => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Select a representation for the `accept` [resolution input
+    /// metadata](https://www.w3.org/TR/did-core/#did-resolution-input-metadata) property.
+    ///
+    /// `accept` may list several media types, as in an HTTP `Accept` header; the first
+    /// supported one is used. Wildcards and a missing value select the JSON-LD representation.
+    pub fn from_accept(accept: Option<&str>) -> Result<Self, RepresentationError> {
+        let accept = match accept {
+            Some(accept) => accept,
+            None => return Ok(Self::JsonLd),
+        };
+        for media_type in accept.split(',') {
+            let essence = media_type.split(';').next().unwrap_or_default().trim();
+            if essence == "*/*" || essence == "application/*" {
+                return Ok(Self::JsonLd);
+            }
+            if let Some(representation) = Self::from_media_type(essence) {
+                return Ok(representation);
+            }
+        }
+        Err(RepresentationError::NotSupported(accept.to_string()))
+    }
+}
+
+impl Document {
+    /// Produce a representation of the DID document.
+    ///
+    /// For JSON-LD, the DID v1 context is added as the first `@context` value if not already
+    /// present. For JSON and CBOR, `@context` is omitted.
+    pub fn to_representation(
+        &self,
+        representation: Representation,
+    ) -> Result<Vec<u8>, RepresentationError> {
+        let mut doc = self.clone();
+        match representation {
+            Representation::JsonLd => {
+                doc.context = Some(with_default_context(doc.context.take()));
+                Ok(serde_json::to_vec(&doc)?)
+            }
+            Representation::Json => {
+                doc.context = None;
+                Ok(serde_json::to_vec(&doc)?)
+            }
+            Representation::Cbor => {
+                doc.context = None;
+                let mut bytes = Vec::new();
+                ciborium::ser::into_writer(&doc, &mut bytes)
+                    .map_err(|e| RepresentationError::Cbor(e.to_string()))?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Consume a representation of a DID document.
+    ///
+    /// For JSON-LD, `@context` is required and its first value must be the DID v1 context. For
+    /// JSON and CBOR, any `@context` is ignored.
+    pub fn from_representation(
+        bytes: &[u8],
+        representation: Representation,
+    ) -> Result<Self, RepresentationError> {
+        match representation {
+            Representation::JsonLd => {
+                let doc: Document = serde_json::from_slice(bytes)?;
+                let first = doc
+                    .context
+                    .as_ref()
+                    .ok_or(RepresentationError::MissingContext)?
+                    .as_slice()
+                    .first()
+                    .cloned();
+                match first {
+                    Some(Context::URI(uri)) if uri == DEFAULT_CONTEXT => Ok(doc),
+                    _ => Err(RepresentationError::InvalidContext),
+                }
+            }
+            Representation::Json => {
+                let mut doc: Document = serde_json::from_slice(bytes)?;
+                doc.context = None;
+                Ok(doc)
+            }
+            Representation::Cbor => {
+                let mut doc: Document = ciborium::de::from_reader(bytes)
+                    .map_err(|e| RepresentationError::Cbor(e.to_string()))?;
+                doc.context = None;
+                Ok(doc)
+            }
+        }
+    }
+}
+
+fn with_default_context(context: Option<OneOrMany<Context>>) -> OneOrMany<Context> {
+    let default_context = Context::URI(DEFAULT_CONTEXT.to_string());
+    let mut contexts = context.map(OneOrMany::into_vec).unwrap_or_default();
+    if contexts.first() != Some(&default_context) {
+        contexts.retain(|context| context != &default_context);
+        contexts.insert(0, default_context);
+    }
+    if contexts.len() == 1 {
+        OneOrMany::One(contexts.remove(0))
+    } else {
+        OneOrMany::Many(contexts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did_resolve::{
+        DIDResolver, DocumentMetadata, ResolutionInputMetadata, ResolutionMetadata,
+    };
+    use crate::error::ErrorCode;
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    fn document() -> Document {
+        serde_json::from_value(json!({
+            "@context": [DEFAULT_CONTEXT, "https://w3id.org/security/suites/jws-2020/v1"],
+            "id": "did:example:123",
+            "verificationMethod": [{
+                "id": "did:example:123#key-1",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:123",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "secp256k1",
+                    "x": "WfY7Px6AgH6x-_dgAoRbg8weYRJA36ON-gQiFnETrqw",
+                    "y": "IzFx3BUGztK0cyDStiunXbrZYYTtKbOUzx16SUK0sAY"
+                }
+            }],
+            "service": [{
+                "id": "did:example:123#linked-domain",
+                "type": "LinkedDomains",
+                "serviceEndpoint": {"origins": ["https://example.com"]}
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn from_media_type() {
+        assert_eq!(
+            Representation::from_media_type("application/did+json"),
+            Some(Representation::Json)
+        );
+        assert_eq!(
+            Representation::from_media_type("Application/DID+LD+JSON; charset=utf-8"),
+            Some(Representation::JsonLd)
+        );
+        assert_eq!(
+            Representation::from_media_type("application/did+cbor"),
+            Some(Representation::Cbor)
+        );
+        assert_eq!(Representation::from_media_type("application/json"), None);
+    }
+
+    #[test]
+    fn from_accept() {
+        assert_eq!(
+            Representation::from_accept(None).unwrap(),
+            Representation::JsonLd
+        );
+        assert_eq!(
+            Representation::from_accept(Some("application/did+cbor")).unwrap(),
+            Representation::Cbor
+        );
+        // The first supported media type in the list is used.
+        assert_eq!(
+            Representation::from_accept(Some(
+                "text/html, application/did+json;q=0.9, application/did+ld+json"
+            ))
+            .unwrap(),
+            Representation::Json
+        );
+        assert_eq!(
+            Representation::from_accept(Some("application/did+cbor; q=0.5")).unwrap(),
+            Representation::Cbor
+        );
+        assert_eq!(
+            Representation::from_accept(Some("text/html, */*")).unwrap(),
+            Representation::JsonLd
+        );
+        assert_eq!(
+            Representation::from_accept(Some("application/*")).unwrap(),
+            Representation::JsonLd
+        );
+        assert!(matches!(
+            Representation::from_accept(Some("text/html, application/json")),
+            Err(RepresentationError::NotSupported(accept)) if accept == "text/html, application/json"
+        ));
+    }
+
+    #[test]
+    fn json_omits_context() {
+        let bytes = document().to_representation(Representation::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(value.get("@context").is_none());
+        assert_eq!(value["id"], "did:example:123");
+
+        let doc = Document::from_representation(&bytes, Representation::Json).unwrap();
+        assert_eq!(doc.context, None);
+        // @context in the JSON representation is ignored.
+        let with_context = serde_json::to_vec(&document()).unwrap();
+        let doc = Document::from_representation(&with_context, Representation::Json).unwrap();
+        assert_eq!(doc.context, None);
+    }
+
+    #[test]
+    fn json_ld_enforces_context() {
+        let mut doc = document();
+        doc.context = None;
+        let bytes = doc.to_representation(Representation::JsonLd).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(value["@context"], DEFAULT_CONTEXT);
+
+        // The DID v1 context is moved to the front.
+        doc.context = Some(OneOrMany::Many(vec![
+            Context::URI("https://example.org/context".to_string()),
+            Context::URI(DEFAULT_CONTEXT.to_string()),
+        ]));
+        let bytes = doc.to_representation(Representation::JsonLd).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            value["@context"],
+            json!([DEFAULT_CONTEXT, "https://example.org/context"])
+        );
+
+        let doc = Document::from_representation(&bytes, Representation::JsonLd).unwrap();
+        assert!(doc.context.is_some());
+        let round_trip = document()
+            .to_representation(Representation::JsonLd)
+            .unwrap();
+        assert_eq!(
+            Document::from_representation(&round_trip, Representation::JsonLd).unwrap(),
+            document()
+        );
+
+        let no_context = serde_json::to_vec(&json!({"id": "did:example:123"})).unwrap();
+        assert!(matches!(
+            Document::from_representation(&no_context, Representation::JsonLd),
+            Err(RepresentationError::MissingContext)
+        ));
+        let wrong_context = serde_json::to_vec(&json!({
+            "@context": ["https://example.org/context", DEFAULT_CONTEXT],
+            "id": "did:example:123"
+        }))
+        .unwrap();
+        assert!(matches!(
+            Document::from_representation(&wrong_context, Representation::JsonLd),
+            Err(RepresentationError::InvalidContext)
+        ));
+    }
+
+    #[test]
+    fn cbor_round_trip() {
+        let bytes = document().to_representation(Representation::Cbor).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&bytes).is_err());
+        let doc = Document::from_representation(&bytes, Representation::Cbor).unwrap();
+        let mut expected = document();
+        expected.context = None;
+        assert_eq!(doc, expected);
+
+        assert!(matches!(
+            Document::from_representation(b"not cbor", Representation::Cbor),
+            Err(RepresentationError::Cbor(_))
+        ));
+    }
+
+    struct ExampleResolver;
+
+    #[async_trait]
+    impl DIDResolver for ExampleResolver {
+        async fn resolve(
+            &self,
+            _did: &str,
+            _input_metadata: &ResolutionInputMetadata,
+        ) -> (
+            ResolutionMetadata,
+            Option<Document>,
+            Option<DocumentMetadata>,
+        ) {
+            (ResolutionMetadata::default(), Some(document()), None)
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_representation() {
+        let input_metadata = ResolutionInputMetadata {
+            accept: Some(TYPE_DID_CBOR.to_string()),
+            ..Default::default()
+        };
+        let (res_meta, bytes, _) = ExampleResolver
+            .resolve_representation("did:example:123", &input_metadata)
+            .await;
+        assert_eq!(res_meta.error, None);
+        assert_eq!(res_meta.content_type.as_deref(), Some(TYPE_DID_CBOR));
+        let doc = Document::from_representation(&bytes, Representation::Cbor).unwrap();
+        assert_eq!(doc.id, "did:example:123");
+
+        let input_metadata = ResolutionInputMetadata {
+            accept: Some("text/html".to_string()),
+            ..Default::default()
+        };
+        let (res_meta, bytes, _) = ExampleResolver
+            .resolve_representation("did:example:123", &input_metadata)
+            .await;
+        assert_eq!(res_meta.error, Some(ErrorCode::RepresentationNotSupported));
+        assert!(bytes.is_empty());
+    }
+}
@@ -0,0 +1,646 @@
+//! Typed models for common [service](https://www.w3.org/TR/did-core/#services) types
+//!
+//! [Service] keeps the service endpoint as untyped URIs and maps. [TypedService] interprets
+//! the service types registered in [DID Specification Registries §Service
+//! Types](https://www.w3.org/TR/did-spec-registries/#service-types) that applications commonly
+//! rely on, validating their endpoints. Other service types are kept as they are.
+
+use std::convert::TryFrom;
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    validate::{is_valid_did_url, is_valid_uri},
+    Document, OneOrMany, Service, ServiceEndpoint,
+};
+
+/// Service type for [LinkedDomains]
+pub const TYPE_LINKED_DOMAINS: &str = "LinkedDomains";
+
+/// Service type for [DIDCommMessaging]
+pub const TYPE_DIDCOMM_MESSAGING: &str = "DIDCommMessaging";
+
+/// Service type for [CredentialRegistry]
+pub const TYPE_CREDENTIAL_REGISTRY: &str = "CredentialRegistry";
+
+/// Error interpreting a [Service] as a [TypedService]
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ServiceError {
+    /// The service has no `serviceEndpoint`
+    #[error("Missing serviceEndpoint for {0} service")]
+    MissingEndpoint(&'static str),
+    /// The service endpoint does not have the structure defined for the service type
+    #[error("Invalid serviceEndpoint for {0} service: {1}")]
+    InvalidEndpoint(&'static str, String),
+    /// A LinkedDomains origin is not a web origin
+    #[error("Expected origin but found '{0}'")]
+    InvalidOrigin(String),
+    /// A URI value is not a valid URI
+    #[error("Expected URI but found '{0}'")]
+    InvalidUri(String),
+    /// A DIDComm routing key is not a DID URL
+    #[error("Expected DID URL for routing key but found '{0}'")]
+    InvalidRoutingKey(String),
+}
+
+/// A [Service] of a known type, with a validated service endpoint
+///
+/// Converted from a [Service] by the first known type in its `type` property. Additional service
+/// properties and service types are not kept by the typed variants.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedService {
+    LinkedDomains(LinkedDomains),
+    DIDCommMessaging(DIDCommMessaging),
+    CredentialRegistry(CredentialRegistry),
+    /// Service of another type, kept untyped
+    Other(Service),
+}
+
+/// [`LinkedDomains`](https://identity.foundation/.well-known/resources/did-configuration/#linked-domain-service-endpoint)
+/// service, linking the DID to web origins
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkedDomains {
+    /// Service id
+    pub id: String,
+    /// Linked origins, e.g. `https://example.com`
+    pub origins: Vec<String>,
+}
+
+/// [`DIDCommMessaging`](https://identity.foundation/didcomm-messaging/spec/v2.1/#did-document-service-endpoint)
+/// service, for receiving DIDComm v2 messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct DIDCommMessaging {
+    /// Service id
+    pub id: String,
+    /// Endpoints, in order of preference
+    pub endpoints: Vec<DIDCommEndpoint>,
+}
+
+/// Endpoint of a [DIDCommMessaging] service
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DIDCommEndpoint {
+    /// URI of the endpoint, or a DID for mediation
+    pub uri: String,
+    /// Accepted media types for message envelopes, e.g. `didcomm/v2`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accept: Vec<String>,
+    /// DID URLs of the key agreement keys of mediators, in routing order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routing_keys: Vec<String>,
+}
+
+/// [`CredentialRegistry`](https://www.w3.org/TR/did-spec-registries/#credentialregistry)
+/// service, pointing to registries of credentials about the DID subject
+#[derive(Debug, Clone, PartialEq)]
+pub struct CredentialRegistry {
+    /// Service id
+    pub id: String,
+    /// Registry URIs
+    pub endpoints: Vec<String>,
+}
+
+/// Check that a string is a web origin: an HTTP(S) URL with only scheme, host and port.
+fn validate_origin(origin: &str) -> Result<(), ServiceError> {
+    let invalid = || ServiceError::InvalidOrigin(origin.to_string());
+    let url = Url::parse(origin).map_err(|_| invalid())?;
+    let is_origin = matches!(url.scheme(), "http" | "https")
+        && url.host().is_some()
+        && url.username().is_empty()
+        && url.password().is_none()
+        && url.path() == "/"
+        && url.query().is_none()
+        && url.fragment().is_none();
+    if !is_origin {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+fn validate_uri(uri: &str) -> Result<(), ServiceError> {
+    if !is_valid_uri(uri) {
+        return Err(ServiceError::InvalidUri(uri.to_string()));
+    }
+    Ok(())
+}
+
+fn endpoints<'a>(
+    service: &'a Service,
+    type_: &'static str,
+) -> Result<&'a [ServiceEndpoint], ServiceError> {
+    match &service.service_endpoint {
+        Some(endpoints) if !endpoints.as_slice().is_empty() => Ok(endpoints.as_slice()),
+        _ => Err(ServiceError::MissingEndpoint(type_)),
+    }
+}
+
+fn to_endpoints(mut endpoints: Vec<ServiceEndpoint>) -> Option<OneOrMany<ServiceEndpoint>> {
+    if endpoints.len() == 1 {
+        endpoints.pop().map(OneOrMany::One)
+    } else {
+        Some(OneOrMany::Many(endpoints))
+    }
+}
+
+impl LinkedDomains {
+    /// Interpret a service as LinkedDomains.
+    ///
+    /// The service endpoint is an origin, a set of origins, or a map with an `origins` set.
+    pub fn from_service(service: &Service) -> Result<Self, ServiceError> {
+        let mut origins = Vec::new();
+        for endpoint in endpoints(service, TYPE_LINKED_DOMAINS)? {
+            match endpoint {
+                ServiceEndpoint::URI(origin) => origins.push(origin.clone()),
+                ServiceEndpoint::Map(map) => match map.get("origins") {
+                    Some(Value::Array(values)) => {
+                        for value in values {
+                            match value {
+                                Value::String(origin) => origins.push(origin.clone()),
+                                _ => {
+                                    return Err(ServiceError::InvalidEndpoint(
+                                        TYPE_LINKED_DOMAINS,
+                                        "origins must be strings".to_string(),
+                                    ))
+                                }
+                            }
+                        }
+                    }
+                    _ => {
+                        return Err(ServiceError::InvalidEndpoint(
+                            TYPE_LINKED_DOMAINS,
+                            "expected origin or map with origins".to_string(),
+                        ))
+                    }
+                },
+            }
+        }
+        let linked_domains = Self {
+            id: service.id.clone(),
+            origins,
+        };
+        linked_domains.validate()?;
+        Ok(linked_domains)
+    }
+
+    /// Check that there is at least one origin and that all origins are valid.
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        if self.origins.is_empty() {
+            return Err(ServiceError::MissingEndpoint(TYPE_LINKED_DOMAINS));
+        }
+        self.origins
+            .iter()
+            .try_for_each(|origin| validate_origin(origin))
+    }
+}
+
+impl DIDCommMessaging {
+    /// Interpret a service as DIDCommMessaging.
+    ///
+    /// The service endpoint is a map with `uri`, and optionally `accept` and `routingKeys`, or a
+    /// set of such maps.
+    pub fn from_service(service: &Service) -> Result<Self, ServiceError> {
+        let endpoints = endpoints(service, TYPE_DIDCOMM_MESSAGING)?
+            .iter()
+            .map(|endpoint| match endpoint {
+                ServiceEndpoint::Map(map) => serde_json::from_value(map.clone()).map_err(|e| {
+                    ServiceError::InvalidEndpoint(TYPE_DIDCOMM_MESSAGING, e.to_string())
+                }),
+                ServiceEndpoint::URI(_) => Err(ServiceError::InvalidEndpoint(
+                    TYPE_DIDCOMM_MESSAGING,
+                    "expected map with uri".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<DIDCommEndpoint>, _>>()?;
+        let didcomm = Self {
+            id: service.id.clone(),
+            endpoints,
+        };
+        didcomm.validate()?;
+        Ok(didcomm)
+    }
+
+    /// Check that there is at least one endpoint, that endpoint URIs are valid and that routing
+    /// keys are DID URLs.
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        if self.endpoints.is_empty() {
+            return Err(ServiceError::MissingEndpoint(TYPE_DIDCOMM_MESSAGING));
+        }
+        for endpoint in &self.endpoints {
+            validate_uri(&endpoint.uri)?;
+            if let Some(key) = endpoint
+                .routing_keys
+                .iter()
+                .find(|key| !is_valid_did_url(key))
+            {
+                return Err(ServiceError::InvalidRoutingKey(key.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CredentialRegistry {
+    /// Interpret a service as CredentialRegistry.
+    ///
+    /// The service endpoint is a URI or a set of URIs.
+    pub fn from_service(service: &Service) -> Result<Self, ServiceError> {
+        let endpoints = endpoints(service, TYPE_CREDENTIAL_REGISTRY)?
+            .iter()
+            .map(|endpoint| match endpoint {
+                ServiceEndpoint::URI(uri) => Ok(uri.clone()),
+                ServiceEndpoint::Map(_) => Err(ServiceError::InvalidEndpoint(
+                    TYPE_CREDENTIAL_REGISTRY,
+                    "expected URI".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let registry = Self {
+            id: service.id.clone(),
+            endpoints,
+        };
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Check that there is at least one endpoint and that all endpoints are valid URIs.
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        if self.endpoints.is_empty() {
+            return Err(ServiceError::MissingEndpoint(TYPE_CREDENTIAL_REGISTRY));
+        }
+        self.endpoints.iter().try_for_each(|uri| validate_uri(uri))
+    }
+}
+
+impl TypedService {
+    /// Service id
+    pub fn id(&self) -> &str {
+        match self {
+            Self::LinkedDomains(service) => &service.id,
+            Self::DIDCommMessaging(service) => &service.id,
+            Self::CredentialRegistry(service) => &service.id,
+            Self::Other(service) => &service.id,
+        }
+    }
+}
+
+impl TryFrom<&Service> for TypedService {
+    type Error = ServiceError;
+
+    fn try_from(service: &Service) -> Result<Self, Self::Error> {
+        for type_ in service.type_.iter() {
+            match type_.as_str() {
+                TYPE_LINKED_DOMAINS => {
+                    return LinkedDomains::from_service(service).map(Self::LinkedDomains)
+                }
+                TYPE_DIDCOMM_MESSAGING => {
+                    return DIDCommMessaging::from_service(service).map(Self::DIDCommMessaging)
+                }
+                TYPE_CREDENTIAL_REGISTRY => {
+                    return CredentialRegistry::from_service(service).map(Self::CredentialRegistry)
+                }
+                _ => {}
+            }
+        }
+        Ok(Self::Other(service.clone()))
+    }
+}
+
+impl From<TypedService> for Service {
+    fn from(service: TypedService) -> Self {
+        let (id, type_, endpoints) = match service {
+            TypedService::LinkedDomains(LinkedDomains { id, mut origins }) => {
+                let endpoint = if origins.len() == 1 {
+                    ServiceEndpoint::URI(origins.remove(0))
+                } else {
+                    ServiceEndpoint::Map(serde_json::json!({ "origins": origins }))
+                };
+                (id, TYPE_LINKED_DOMAINS, vec![endpoint])
+            }
+            TypedService::DIDCommMessaging(DIDCommMessaging { id, endpoints }) => {
+                let endpoints = endpoints
+                    .into_iter()
+                    .map(|endpoint| {
+                        ServiceEndpoint::Map(serde_json::to_value(endpoint).unwrap_or_default())
+                    })
+                    .collect();
+                (id, TYPE_DIDCOMM_MESSAGING, endpoints)
+            }
+            TypedService::CredentialRegistry(CredentialRegistry { id, endpoints }) => {
+                let endpoints = endpoints.into_iter().map(ServiceEndpoint::URI).collect();
+                (id, TYPE_CREDENTIAL_REGISTRY, endpoints)
+            }
+            TypedService::Other(service) => return service,
+        };
+        Service {
+            id,
+            type_: OneOrMany::One(type_.to_string()),
+            service_endpoint: to_endpoints(endpoints),
+            property_set: None,
+        }
+    }
+}
+
+impl Service {
+    /// Whether the service has the given type, among its types.
+    pub fn has_type(&self, type_: &str) -> bool {
+        self.type_.iter().any(|t| t == type_)
+    }
+
+    /// Interpret the service by its type; see [TypedService].
+    pub fn to_typed(&self) -> Result<TypedService, ServiceError> {
+        TypedService::try_from(self)
+    }
+}
+
+impl Document {
+    /// Get the services that have the given type.
+    pub fn services_by_type(&self, type_: &str) -> Vec<&Service> {
+        self.service
+            .iter()
+            .flatten()
+            .filter(|service| service.has_type(type_))
+            .collect()
+    }
+
+    /// Get a service by absolute or relative id.
+    pub fn find_service(&self, id: &str) -> Option<&Service> {
+        let id = crate::absolute_did_url(&self.id, id);
+        self.service
+            .iter()
+            .flatten()
+            .find(|service| crate::absolute_did_url(&self.id, &service.id) == id)
+    }
+
+    /// Get the [LinkedDomains] services, skipping services that are not valid.
+    pub fn linked_domains(&self) -> Vec<LinkedDomains> {
+        self.services_by_type(TYPE_LINKED_DOMAINS)
+            .into_iter()
+            .filter_map(|service| LinkedDomains::from_service(service).ok())
+            .collect()
+    }
+
+    /// Get the [DIDCommMessaging] services, skipping services that are not valid.
+    pub fn didcomm_messaging(&self) -> Vec<DIDCommMessaging> {
+        self.services_by_type(TYPE_DIDCOMM_MESSAGING)
+            .into_iter()
+            .filter_map(|service| DIDCommMessaging::from_service(service).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn service(value: Value) -> Service {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn linked_domains(endpoint: Value) -> Service {
+        service(json!({
+            "id": "#linked-domain",
+            "type": "LinkedDomains",
+            "serviceEndpoint": endpoint
+        }))
+    }
+
+    fn didcomm(endpoint: Value) -> Service {
+        service(json!({
+            "id": "#didcomm",
+            "type": "DIDCommMessaging",
+            "serviceEndpoint": endpoint
+        }))
+    }
+
+    #[test]
+    fn linked_domains_endpoints() {
+        let expected = TypedService::LinkedDomains(LinkedDomains {
+            id: "#linked-domain".to_string(),
+            origins: vec![
+                "https://example.com".to_string(),
+                "https://example.org:8443".to_string(),
+            ],
+        });
+        let endpoints = [
+            json!(["https://example.com", "https://example.org:8443"]),
+            json!({"origins": ["https://example.com", "https://example.org:8443"]}),
+            json!(["https://example.com", {"origins": ["https://example.org:8443"]}]),
+        ];
+        for endpoint in endpoints {
+            assert_eq!(linked_domains(endpoint).to_typed().unwrap(), expected);
+        }
+        assert_eq!(
+            linked_domains(json!("https://example.com"))
+                .to_typed()
+                .unwrap()
+                .id(),
+            "#linked-domain"
+        );
+    }
+
+    #[test]
+    fn linked_domains_origin_validation() {
+        for origin in [
+            "https://example.com/path",
+            "https://example.com/?query",
+            "https://example.com/#fragment",
+            "https://user@example.com",
+            "ftp://example.com",
+            "example.com",
+        ] {
+            assert_eq!(
+                linked_domains(json!(origin)).to_typed(),
+                Err(ServiceError::InvalidOrigin(origin.to_string())),
+                "{}",
+                origin
+            );
+        }
+        assert!(matches!(
+            linked_domains(json!({"origins": [1]})).to_typed(),
+            Err(ServiceError::InvalidEndpoint(TYPE_LINKED_DOMAINS, _))
+        ));
+        assert!(matches!(
+            linked_domains(json!({"uri": "https://example.com"})).to_typed(),
+            Err(ServiceError::InvalidEndpoint(TYPE_LINKED_DOMAINS, _))
+        ));
+        assert_eq!(
+            linked_domains(json!({"origins": []})).to_typed(),
+            Err(ServiceError::MissingEndpoint(TYPE_LINKED_DOMAINS))
+        );
+        let mut no_endpoint = linked_domains(json!("https://example.com"));
+        no_endpoint.service_endpoint = None;
+        assert_eq!(
+            no_endpoint.to_typed(),
+            Err(ServiceError::MissingEndpoint(TYPE_LINKED_DOMAINS))
+        );
+    }
+
+    #[test]
+    fn didcomm_messaging_endpoints() {
+        let typed = didcomm(json!([
+            {
+                "uri": "https://example.com/didcomm",
+                "accept": ["didcomm/v2"],
+                "routingKeys": ["did:example:mediator#key-1"]
+            },
+            {"uri": "did:example:mediator"}
+        ]))
+        .to_typed()
+        .unwrap();
+        assert_eq!(
+            typed,
+            TypedService::DIDCommMessaging(DIDCommMessaging {
+                id: "#didcomm".to_string(),
+                endpoints: vec![
+                    DIDCommEndpoint {
+                        uri: "https://example.com/didcomm".to_string(),
+                        accept: vec!["didcomm/v2".to_string()],
+                        routing_keys: vec!["did:example:mediator#key-1".to_string()],
+                    },
+                    DIDCommEndpoint {
+                        uri: "did:example:mediator".to_string(),
+                        accept: Vec::new(),
+                        routing_keys: Vec::new(),
+                    },
+                ],
+            })
+        );
+
+        assert_eq!(
+            didcomm(json!({"uri": "https://example.com", "routingKeys": ["not a key"]})).to_typed(),
+            Err(ServiceError::InvalidRoutingKey("not a key".to_string()))
+        );
+        assert_eq!(
+            didcomm(json!({"uri": "example"})).to_typed(),
+            Err(ServiceError::InvalidUri("example".to_string()))
+        );
+        assert!(matches!(
+            didcomm(json!("https://example.com/didcomm")).to_typed(),
+            Err(ServiceError::InvalidEndpoint(TYPE_DIDCOMM_MESSAGING, _))
+        ));
+        assert!(matches!(
+            didcomm(json!({"accept": ["didcomm/v2"]})).to_typed(),
+            Err(ServiceError::InvalidEndpoint(TYPE_DIDCOMM_MESSAGING, _))
+        ));
+    }
+
+    #[test]
+    fn credential_registry_endpoints() {
+        let registry = service(json!({
+            "id": "#registry",
+            "type": "CredentialRegistry",
+            "serviceEndpoint": ["https://example.com/registry", "urn:example:registry"]
+        }));
+        assert_eq!(
+            registry.to_typed().unwrap(),
+            TypedService::CredentialRegistry(CredentialRegistry {
+                id: "#registry".to_string(),
+                endpoints: vec![
+                    "https://example.com/registry".to_string(),
+                    "urn:example:registry".to_string(),
+                ],
+            })
+        );
+        let mut map_endpoint = registry;
+        map_endpoint.service_endpoint = Some(OneOrMany::One(ServiceEndpoint::Map(json!({}))));
+        assert!(matches!(
+            map_endpoint.to_typed(),
+            Err(ServiceError::InvalidEndpoint(TYPE_CREDENTIAL_REGISTRY, _))
+        ));
+    }
+
+    #[test]
+    fn other_service_types() {
+        let other = service(json!({
+            "id": "#other",
+            "type": ["ExampleService", "AnotherService"],
+            "serviceEndpoint": {"anything": true},
+            "extra": "property"
+        }));
+        assert_eq!(
+            other.to_typed().unwrap(),
+            TypedService::Other(other.clone())
+        );
+        assert_eq!(Service::from(other.to_typed().unwrap()), other);
+
+        // The first known type is used.
+        let mixed = service(json!({
+            "id": "#mixed",
+            "type": ["ExampleService", "LinkedDomains"],
+            "serviceEndpoint": "https://example.com"
+        }));
+        assert!(matches!(
+            mixed.to_typed().unwrap(),
+            TypedService::LinkedDomains(_)
+        ));
+    }
+
+    #[test]
+    fn typed_service_to_service() {
+        let services = [
+            linked_domains(json!("https://example.com")),
+            linked_domains(json!({"origins": ["https://example.com", "https://example.org"]})),
+            didcomm(json!({"uri": "https://example.com/didcomm", "accept": ["didcomm/v2"]})),
+            didcomm(json!([
+                {"uri": "https://example.com/didcomm"},
+                {"uri": "https://example.org/didcomm"}
+            ])),
+            service(json!({
+                "id": "#registry",
+                "type": "CredentialRegistry",
+                "serviceEndpoint": "https://example.com/registry"
+            })),
+        ];
+        for service in services {
+            let typed = service.to_typed().unwrap();
+            let converted = Service::from(typed.clone());
+            assert_eq!(
+                serde_json::to_value(&converted).unwrap(),
+                serde_json::to_value(&service).unwrap()
+            );
+            assert_eq!(converted.to_typed().unwrap(), typed);
+        }
+    }
+
+    #[test]
+    fn document_services() {
+        let doc: Document = serde_json::from_value(json!({
+            "id": "did:example:123",
+            "service": [
+                {
+                    "id": "#linked-domain",
+                    "type": "LinkedDomains",
+                    "serviceEndpoint": "https://example.com"
+                },
+                {
+                    "id": "did:example:123#invalid",
+                    "type": "LinkedDomains",
+                    "serviceEndpoint": "https://example.com/path"
+                },
+                {
+                    "id": "#didcomm",
+                    "type": "DIDCommMessaging",
+                    "serviceEndpoint": {"uri": "https://example.com/didcomm"}
+                }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(doc.services_by_type(TYPE_LINKED_DOMAINS).len(), 2);
+        assert_eq!(doc.linked_domains().len(), 1);
+        assert_eq!(doc.didcomm_messaging()[0].id, "#didcomm");
+        assert!(doc.services_by_type(TYPE_CREDENTIAL_REGISTRY).is_empty());
+        assert_eq!(
+            doc.find_service("did:example:123#didcomm").unwrap().id,
+            "#didcomm"
+        );
+        assert_eq!(
+            doc.find_service("#invalid").unwrap().id,
+            "did:example:123#invalid"
+        );
+        assert!(doc.find_service("#missing").is_none());
+    }
+}
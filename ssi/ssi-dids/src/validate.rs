@@ -0,0 +1,1098 @@
+//! DID document conformance validation
+//!
+//! Checks a DID document against the data model of [DID Core][did-core], collecting every
+//! problem found as a [Finding] instead of stopping at the first one. Documents are checked in
+//! their JSON representation, so that values the [Document] type cannot hold, such as a missing
+//! property or a value of the wrong type, are reported as findings too.
+//!
+//! [did-core]: https://www.w3.org/TR/did-core/
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{absolute_did_url, Document, Service, VerificationRelationship};
+
+/// Verification method types known to this validator
+///
+/// From the [DID Specification Registries](https://www.w3.org/TR/did-spec-registries/#verification-method-types),
+/// plus `JsonWebSignature2020`, which Sidetree-based methods such as ION use as a verification
+/// method type.
+pub const KNOWN_VERIFICATION_METHOD_TYPES: &[&str] = &[
+    "JsonWebKey2020",
+    "JsonWebSignature2020",
+    "Multikey",
+    "EcdsaSecp256k1VerificationKey2019",
+    "EcdsaSecp256k1RecoveryMethod2020",
+    "EcdsaSecp256r1VerificationKey2019",
+    "Ed25519VerificationKey2018",
+    "Ed25519VerificationKey2020",
+    "X25519KeyAgreementKey2019",
+    "X25519KeyAgreementKey2020",
+    "Bls12381G1Key2020",
+    "Bls12381G2Key2020",
+    "RsaVerificationKey2018",
+];
+
+/// Severity of a [Finding]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// The document does not conform to DID Core.
+    Error,
+    /// The document conforms, but may not be understood by consumers.
+    Warning,
+}
+
+/// Kind of problem reported by a [Finding]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FindingCode {
+    /// The document is not a JSON object
+    InvalidDocument,
+    /// A required property is missing
+    MissingProperty,
+    /// A property value has the wrong JSON type
+    InvalidPropertyType,
+    /// `id` is not a valid DID
+    InvalidId,
+    /// A controller is not a valid DID
+    InvalidController,
+    /// An `alsoKnownAs` value is not a URI
+    InvalidAlsoKnownAs,
+    /// A verification method id is not a valid DID URL
+    InvalidVerificationMethodId,
+    /// Two verification methods have the same id
+    DuplicateVerificationMethodId,
+    /// A reference to a verification method in this document does not resolve
+    UnresolvedReference,
+    /// A `publicKeyJwk` is not a JWK
+    InvalidPublicKeyJwk,
+    /// A `publicKeyJwk` contains private key parameters
+    PrivateKeyMaterial,
+    /// A verification method has a type that is not known to this validator
+    UnknownVerificationMethodType,
+    /// A service id is not a URI
+    InvalidServiceId,
+    /// A service type is empty
+    InvalidServiceType,
+    /// Two services have the same id
+    DuplicateServiceId,
+    /// A service endpoint is missing, or is not a URI, a map, or a set of these
+    InvalidServiceEndpoint,
+}
+
+/// A problem found in a DID document
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub severity: Severity,
+    pub code: FindingCode,
+    /// JSON Pointer to the offending value in the document
+    pub path: String,
+    pub message: String,
+}
+
+impl Finding {
+    fn error(code: FindingCode, path: String, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            path,
+            message,
+        }
+    }
+
+    fn warning(code: FindingCode, path: String, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            code,
+            path,
+            message,
+        }
+    }
+}
+
+/// Check whether a string conforms to the [DID Syntax](https://www.w3.org/TR/did-core/#did-syntax).
+pub fn is_valid_did(did: &str) -> bool {
+    let rest = match did.strip_prefix("did:") {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let (method_name, method_specific_id) = match rest.split_once(':') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    if method_name.is_empty()
+        || !method_name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+    {
+        return false;
+    }
+    // method-specific-id = *( *idchar ":" ) 1*idchar
+    if method_specific_id.is_empty() || method_specific_id.ends_with(':') {
+        return false;
+    }
+    let bytes = method_specific_id.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                if i + 2 >= bytes.len()
+                    || !bytes[i + 1].is_ascii_hexdigit()
+                    || !bytes[i + 2].is_ascii_hexdigit()
+                {
+                    return false;
+                }
+                i += 3;
+                continue;
+            }
+            b if b.is_ascii_alphanumeric() || b".-_:".contains(&b) => {}
+            _ => return false,
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Check whether a string conforms to the [DID URL Syntax](https://www.w3.org/TR/did-core/#did-url-syntax).
+pub fn is_valid_did_url(did_url: &str) -> bool {
+    let end = did_url.find(['/', '?', '#']);
+    let (did, rest) = did_url.split_at(end.unwrap_or(did_url.len()));
+    is_valid_did(did) && rest.chars().all(is_uri_char)
+}
+
+/// Check whether a string looks like an absolute URI: a scheme followed by URI characters.
+pub fn is_valid_uri(uri: &str) -> bool {
+    let (scheme, rest) = match uri.split_once(':') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let mut scheme_chars = scheme.chars();
+    match scheme_chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+    scheme_chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        && !rest.is_empty()
+        && rest.chars().all(is_uri_char)
+}
+
+fn is_uri_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=%".contains(c)
+}
+
+/// Check whether a verification method or service id is valid: either an absolute URI or a
+/// relative reference such as `#key-1`.
+fn is_valid_id_reference(id: &str) -> bool {
+    match id.strip_prefix('#') {
+        Some(fragment) => !fragment.is_empty() && fragment.chars().all(is_uri_char),
+        None => is_valid_uri(id),
+    }
+}
+
+/// JWK members holding private key material, per [RFC 7518](https://www.rfc-editor.org/rfc/rfc7518#section-6)
+const PRIVATE_JWK_MEMBERS: &[&str] = &["d", "p", "q", "dp", "dq", "qi", "oth", "k"];
+
+/// Get a string or a set of strings, with the path of each string. Other values are reported
+/// with the given code.
+fn strings<'a>(
+    findings: &mut Vec<Finding>,
+    value: &'a Value,
+    path: &str,
+    code: FindingCode,
+) -> Vec<(&'a str, String)> {
+    match value {
+        Value::String(string) => vec![(string.as_str(), path.to_string())],
+        Value::Array(values) => values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| match value {
+                Value::String(string) => Some((string.as_str(), format!("{path}/{i}"))),
+                _ => {
+                    findings.push(Finding::error(
+                        code,
+                        format!("{path}/{i}"),
+                        "Expected string".to_string(),
+                    ));
+                    None
+                }
+            })
+            .collect(),
+        _ => {
+            findings.push(Finding::error(
+                code,
+                path.to_string(),
+                "Expected string or set of strings".to_string(),
+            ));
+            Vec::new()
+        }
+    }
+}
+
+/// Get a required string property of a map, reporting it if it is missing or not a string.
+fn string_property<'a>(
+    findings: &mut Vec<Finding>,
+    map: &'a Map<String, Value>,
+    name: &str,
+    path: &str,
+) -> Option<&'a str> {
+    match map.get(name) {
+        Some(Value::String(string)) => Some(string),
+        Some(_) => {
+            findings.push(Finding::error(
+                FindingCode::InvalidPropertyType,
+                format!("{path}/{name}"),
+                format!("{name} must be a string"),
+            ));
+            None
+        }
+        None => {
+            findings.push(Finding::error(
+                FindingCode::MissingProperty,
+                path.to_string(),
+                format!("Missing {name}"),
+            ));
+            None
+        }
+    }
+}
+
+/// Get the items of an optional array property, reporting it if it is not an array.
+fn array_property<'a>(
+    findings: &mut Vec<Finding>,
+    map: &'a Map<String, Value>,
+    name: &str,
+) -> &'a [Value] {
+    match map.get(name) {
+        Some(Value::Array(values)) => values,
+        Some(_) => {
+            findings.push(Finding::error(
+                FindingCode::InvalidPropertyType,
+                format!("/{name}"),
+                format!("{name} must be a set"),
+            ));
+            &[]
+        }
+        None => &[],
+    }
+}
+
+/// Validate a DID document against DID Core.
+///
+/// Returns all findings; the document conforms if none of them is an [error](Severity::Error).
+pub fn validate_document(doc: &Document) -> Vec<Finding> {
+    match serde_json::to_value(doc) {
+        Ok(value) => validate_value(&value),
+        Err(err) => vec![Finding::error(
+            FindingCode::InvalidDocument,
+            String::new(),
+            format!("Unable to serialize DID document: {err}"),
+        )],
+    }
+}
+
+/// Validate a DID document in JSON, such as a file received from elsewhere. A document that is
+/// not valid JSON is reported as a single finding.
+pub fn validate_json(json: &[u8]) -> Vec<Finding> {
+    match serde_json::from_slice(json) {
+        Ok(value) => validate_value(&value),
+        Err(err) => vec![Finding::error(
+            FindingCode::InvalidDocument,
+            String::new(),
+            format!("Unable to parse DID document as JSON: {err}"),
+        )],
+    }
+}
+
+/// Validate a DID document, as a JSON value, against DID Core.
+///
+/// Returns all findings; the document conforms if none of them is an [error](Severity::Error).
+pub fn validate_value(doc: &Value) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let doc = match doc {
+        Value::Object(doc) => doc,
+        _ => {
+            findings.push(Finding::error(
+                FindingCode::InvalidDocument,
+                String::new(),
+                "DID document must be a JSON object".to_string(),
+            ));
+            return findings;
+        }
+    };
+
+    let did = match doc.get("id") {
+        Some(Value::String(did)) => {
+            if !is_valid_did(did) {
+                findings.push(Finding::error(
+                    FindingCode::InvalidId,
+                    "/id".to_string(),
+                    format!("Expected DID but found '{did}'"),
+                ));
+            }
+            did.as_str()
+        }
+        Some(_) => {
+            findings.push(Finding::error(
+                FindingCode::InvalidId,
+                "/id".to_string(),
+                "id must be a string".to_string(),
+            ));
+            ""
+        }
+        None => {
+            findings.push(Finding::error(
+                FindingCode::MissingProperty,
+                String::new(),
+                "Missing id".to_string(),
+            ));
+            ""
+        }
+    };
+
+    if let Some(controller) = doc.get("controller") {
+        for (controller, path) in strings(
+            &mut findings,
+            controller,
+            "/controller",
+            FindingCode::InvalidController,
+        ) {
+            if !is_valid_did(controller) {
+                findings.push(Finding::error(
+                    FindingCode::InvalidController,
+                    path,
+                    format!("Expected DID but found '{controller}'"),
+                ));
+            }
+        }
+    }
+
+    for (i, aka) in array_property(&mut findings, doc, "alsoKnownAs")
+        .iter()
+        .enumerate()
+    {
+        let path = format!("/alsoKnownAs/{i}");
+        match aka {
+            Value::String(aka) if is_valid_uri(aka) => {}
+            Value::String(aka) => findings.push(Finding::error(
+                FindingCode::InvalidAlsoKnownAs,
+                path,
+                format!("Expected URI but found '{aka}'"),
+            )),
+            _ => findings.push(Finding::error(
+                FindingCode::InvalidAlsoKnownAs,
+                path,
+                "Expected URI string".to_string(),
+            )),
+        }
+    }
+
+    // Embedded verification methods, keyed by absolute id, and references to them.
+    let mut vm_ids: HashMap<String, String> = HashMap::new();
+    let mut references = Vec::new();
+    let mut check_vm = |findings: &mut Vec<Finding>, vm: &Map<String, Value>, path: String| {
+        let id = match validate_verification_method(findings, vm, &path) {
+            Some(id) => absolute_did_url(did, id),
+            None => return,
+        };
+        if let Some(first_path) = vm_ids.get(&id) {
+            findings.push(Finding::error(
+                FindingCode::DuplicateVerificationMethodId,
+                format!("{path}/id"),
+                format!("Verification method id '{id}' already used at {first_path}"),
+            ));
+        } else {
+            vm_ids.insert(id, path);
+        }
+    };
+    for (i, vm) in array_property(&mut findings, doc, "verificationMethod")
+        .iter()
+        .enumerate()
+    {
+        let path = format!("/verificationMethod/{i}");
+        match vm {
+            Value::Object(vm) => check_vm(&mut findings, vm, path),
+            Value::String(_) => findings.push(Finding::error(
+                FindingCode::InvalidVerificationMethodId,
+                path,
+                "verificationMethod entries must be embedded verification methods".to_string(),
+            )),
+            _ => findings.push(Finding::error(
+                FindingCode::InvalidPropertyType,
+                path,
+                "Expected verification method map".to_string(),
+            )),
+        }
+    }
+    for rel in VerificationRelationship::ALL {
+        let name = rel.property_name();
+        for (i, vm) in array_property(&mut findings, doc, name).iter().enumerate() {
+            let path = format!("/{name}/{i}");
+            match vm {
+                Value::Object(vm) => check_vm(&mut findings, vm, path),
+                Value::String(reference) => references.push((reference, path)),
+                _ => findings.push(Finding::error(
+                    FindingCode::InvalidPropertyType,
+                    path,
+                    "Expected verification method map or DID URL".to_string(),
+                )),
+            }
+        }
+    }
+
+    for (reference, path) in references {
+        if !is_valid_id_reference(reference) {
+            findings.push(Finding::error(
+                FindingCode::InvalidVerificationMethodId,
+                path,
+                format!("Expected DID URL but found '{reference}'"),
+            ));
+            continue;
+        }
+        let id = absolute_did_url(did, reference);
+        // References to verification methods in other DID documents cannot be checked here.
+        let is_local = id
+            .strip_prefix(did)
+            .is_some_and(|rest| rest.starts_with('#'));
+        if is_local && !vm_ids.contains_key(&id) {
+            findings.push(Finding::error(
+                FindingCode::UnresolvedReference,
+                path,
+                format!("Verification method '{reference}' not found in document"),
+            ));
+        }
+    }
+
+    let mut service_ids: HashMap<String, String> = HashMap::new();
+    for (i, service) in array_property(&mut findings, doc, "service")
+        .iter()
+        .enumerate()
+    {
+        let path = format!("/service/{i}");
+        let service = match service {
+            Value::Object(service) => service,
+            _ => {
+                findings.push(Finding::error(
+                    FindingCode::InvalidPropertyType,
+                    path,
+                    "Expected service map".to_string(),
+                ));
+                continue;
+            }
+        };
+        let id = match validate_service(&mut findings, service, &path) {
+            Some(id) => absolute_did_url(did, id),
+            None => continue,
+        };
+        if let Some(first_path) = service_ids.get(&id) {
+            findings.push(Finding::error(
+                FindingCode::DuplicateServiceId,
+                format!("{path}/id"),
+                format!("Service id '{id}' already used at {first_path}"),
+            ));
+        } else {
+            service_ids.insert(id, path);
+        }
+    }
+
+    findings
+}
+
+/// Validate an embedded verification method. Returns its id, if it has one.
+fn validate_verification_method<'a>(
+    findings: &mut Vec<Finding>,
+    vm: &'a Map<String, Value>,
+    path: &str,
+) -> Option<&'a str> {
+    let id = string_property(findings, vm, "id", path);
+    if let Some(id) = id {
+        if !is_valid_id_reference(id) || (!id.starts_with('#') && !is_valid_did_url(id)) {
+            findings.push(Finding::error(
+                FindingCode::InvalidVerificationMethodId,
+                format!("{path}/id"),
+                format!("Expected DID URL but found '{id}'"),
+            ));
+        }
+    }
+    if let Some(controller) = string_property(findings, vm, "controller", path) {
+        if !is_valid_did(controller) {
+            findings.push(Finding::error(
+                FindingCode::InvalidController,
+                format!("{path}/controller"),
+                format!("Expected DID but found '{controller}'"),
+            ));
+        }
+    }
+    if let Some(type_) = string_property(findings, vm, "type", path) {
+        if !KNOWN_VERIFICATION_METHOD_TYPES.contains(&type_) {
+            findings.push(Finding::warning(
+                FindingCode::UnknownVerificationMethodType,
+                format!("{path}/type"),
+                format!("Unknown verification method type '{type_}'"),
+            ));
+        }
+    }
+    match vm.get("publicKeyJwk") {
+        Some(Value::Object(jwk)) => {
+            if !matches!(jwk.get("kty"), Some(Value::String(_))) {
+                findings.push(Finding::error(
+                    FindingCode::InvalidPublicKeyJwk,
+                    format!("{path}/publicKeyJwk"),
+                    "publicKeyJwk must have a kty".to_string(),
+                ));
+            }
+            let private_members: Vec<&str> = PRIVATE_JWK_MEMBERS
+                .iter()
+                .copied()
+                .filter(|member| jwk.contains_key(*member))
+                .collect();
+            if !private_members.is_empty() {
+                findings.push(Finding::error(
+                    FindingCode::PrivateKeyMaterial,
+                    format!("{path}/publicKeyJwk"),
+                    format!(
+                        "publicKeyJwk must not contain private key parameters: {}",
+                        private_members.join(", ")
+                    ),
+                ));
+            }
+        }
+        Some(_) => findings.push(Finding::error(
+            FindingCode::InvalidPublicKeyJwk,
+            format!("{path}/publicKeyJwk"),
+            "publicKeyJwk must be a JSON object".to_string(),
+        )),
+        None => {}
+    }
+    id
+}
+
+/// Validate a service. Returns its id, if it has one.
+fn validate_service<'a>(
+    findings: &mut Vec<Finding>,
+    service: &'a Map<String, Value>,
+    path: &str,
+) -> Option<&'a str> {
+    let findings_before = findings.len();
+    let id = match service.get("id") {
+        Some(Value::String(id)) => {
+            if !is_valid_id_reference(id) {
+                findings.push(Finding::error(
+                    FindingCode::InvalidServiceId,
+                    format!("{path}/id"),
+                    format!("Expected URI but found '{id}'"),
+                ));
+            }
+            Some(id.as_str())
+        }
+        Some(_) => {
+            findings.push(Finding::error(
+                FindingCode::InvalidServiceId,
+                format!("{path}/id"),
+                "Service id must be a string".to_string(),
+            ));
+            None
+        }
+        None => {
+            findings.push(Finding::error(
+                FindingCode::MissingProperty,
+                path.to_string(),
+                "Missing id".to_string(),
+            ));
+            None
+        }
+    };
+    match service.get("type") {
+        Some(type_) => {
+            let type_path = format!("{path}/type");
+            let types = strings(findings, type_, &type_path, FindingCode::InvalidServiceType);
+            let is_empty_set = matches!(type_, Value::Array(types) if types.is_empty());
+            if is_empty_set || types.iter().any(|(type_, _)| type_.is_empty()) {
+                findings.push(Finding::error(
+                    FindingCode::InvalidServiceType,
+                    type_path,
+                    "Service type must be a non-empty string or set of strings".to_string(),
+                ));
+            }
+        }
+        None => findings.push(Finding::error(
+            FindingCode::MissingProperty,
+            path.to_string(),
+            "Missing type".to_string(),
+        )),
+    }
+    let endpoint_path = format!("{path}/serviceEndpoint");
+    match service.get("serviceEndpoint") {
+        None => findings.push(Finding::error(
+            FindingCode::InvalidServiceEndpoint,
+            path.to_string(),
+            "Missing serviceEndpoint".to_string(),
+        )),
+        Some(Value::Array(endpoints)) => {
+            if endpoints.is_empty() {
+                findings.push(Finding::error(
+                    FindingCode::InvalidServiceEndpoint,
+                    endpoint_path.clone(),
+                    "serviceEndpoint set must not be empty".to_string(),
+                ));
+            }
+            for (i, endpoint) in endpoints.iter().enumerate() {
+                validate_service_endpoint(findings, endpoint, format!("{endpoint_path}/{i}"));
+            }
+        }
+        Some(endpoint) => validate_service_endpoint(findings, endpoint, endpoint_path),
+    }
+    // Check the endpoint structure of known service types, unless already reported.
+    if findings.len() == findings_before {
+        let typed = serde_json::from_value::<Service>(Value::Object(service.clone()))
+            .map_err(|err| err.to_string())
+            .and_then(|service| service.to_typed().map_err(|err| err.to_string()));
+        if let Err(err) = typed {
+            findings.push(Finding::error(
+                FindingCode::InvalidServiceEndpoint,
+                format!("{path}/serviceEndpoint"),
+                err,
+            ));
+        }
+    }
+    id
+}
+
+/// Validate a single service endpoint: a URI or a map.
+fn validate_service_endpoint(findings: &mut Vec<Finding>, endpoint: &Value, path: String) {
+    match endpoint {
+        Value::String(uri) if !is_valid_uri(uri) => findings.push(Finding::error(
+            FindingCode::InvalidServiceEndpoint,
+            path,
+            format!("Expected URI but found '{uri}'"),
+        )),
+        Value::String(_) | Value::Object(_) => {}
+        _ => findings.push(Finding::error(
+            FindingCode::InvalidServiceEndpoint,
+            path,
+            "serviceEndpoint must be a URI, a map, or a set of URIs and/or maps".to_string(),
+        )),
+    }
+}
+
+impl Document {
+    /// Validate the document against DID Core. See [validate_document].
+    pub fn validate(&self) -> Vec<Finding> {
+        validate_document(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const DID: &str = "did:example:123";
+
+    fn valid_document() -> Value {
+        json!({
+            "@context": "https://www.w3.org/ns/did/v1",
+            "id": DID,
+            "controller": "did:example:controller",
+            "alsoKnownAs": ["https://example.com/user"],
+            "verificationMethod": [{
+                "id": "#key-1",
+                "type": "JsonWebKey2020",
+                "controller": DID,
+                "publicKeyJwk": {
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "x": "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"
+                }
+            }],
+            "authentication": ["#key-1", "did:example:other#key-1"],
+            "assertionMethod": [{
+                "id": "did:example:123#key-2",
+                "type": "EcdsaSecp256k1VerificationKey2019",
+                "controller": DID,
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "secp256k1",
+                    "x": "WfY7Px6AgH6x-_dgAoRbg8weYRJA36ON-gQiFnETrqw",
+                    "y": "IzFx3BUGztK0cyDStiunXbrZYYTtKbOUzx16SUK0sAY"
+                }
+            }],
+            "service": [{
+                "id": "#linked-domain",
+                "type": "LinkedDomains",
+                "serviceEndpoint": ["https://example.com", {"origins": ["https://example.org"]}]
+            }]
+        })
+    }
+
+    /// Validate a modification of the valid document, returning the codes and paths found.
+    fn findings(modify: impl FnOnce(&mut Value)) -> Vec<(FindingCode, String)> {
+        let mut doc = valid_document();
+        modify(&mut doc);
+        validate_value(&doc)
+            .into_iter()
+            .map(|finding| (finding.code, finding.path))
+            .collect()
+    }
+
+    fn finding(code: FindingCode, path: &str) -> Vec<(FindingCode, String)> {
+        vec![(code, path.to_string())]
+    }
+
+    #[test]
+    fn valid_document_has_no_findings() {
+        assert_eq!(validate_value(&valid_document()), vec![]);
+        let doc: Document = serde_json::from_value(json!({
+            "id": DID,
+            "verificationMethod": [{
+                "id": "#key-1",
+                "type": "EcdsaSecp256k1VerificationKey2019",
+                "controller": DID,
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "secp256k1",
+                    "x": "WfY7Px6AgH6x-_dgAoRbg8weYRJA36ON-gQiFnETrqw",
+                    "y": "IzFx3BUGztK0cyDStiunXbrZYYTtKbOUzx16SUK0sAY"
+                }
+            }],
+            "authentication": ["#key-1"]
+        }))
+        .unwrap();
+        assert_eq!(doc.validate(), vec![]);
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert_eq!(
+            validate_json(b"{\"id\":")[0].code,
+            FindingCode::InvalidDocument
+        );
+        assert_eq!(
+            validate_value(&json!(["not", "a", "document"]))[0].code,
+            FindingCode::InvalidDocument
+        );
+        assert_eq!(
+            validate_json(valid_document().to_string().as_bytes()),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn id_syntax() {
+        assert_eq!(
+            findings(|doc| doc["id"] = json!("did:Example:123")),
+            finding(FindingCode::InvalidId, "/id")
+        );
+        assert_eq!(
+            findings(|doc| doc["id"] = json!(123)),
+            finding(FindingCode::InvalidId, "/id")
+        );
+        assert_eq!(
+            findings(|doc| {
+                doc.as_object_mut().unwrap().remove("id");
+            }),
+            finding(FindingCode::MissingProperty, "")
+        );
+    }
+
+    #[test]
+    fn is_valid_did_syntax() {
+        assert!(is_valid_did("did:example:123"));
+        assert!(is_valid_did("did:web:example.com%3A8080:user"));
+        assert!(!is_valid_did("did:example:"));
+        assert!(!is_valid_did("did:example:12%3"));
+        assert!(!is_valid_did("did:example:a b"));
+        assert!(is_valid_did_url("did:example:123/path?query#fragment"));
+        assert!(!is_valid_did_url("did:example:123#frag ment"));
+    }
+
+    #[test]
+    fn controller_syntax() {
+        assert_eq!(
+            findings(|doc| doc["controller"] = json!("example")),
+            finding(FindingCode::InvalidController, "/controller")
+        );
+        assert_eq!(
+            findings(|doc| doc["controller"] = json!([DID, "example", 1])),
+            [
+                (FindingCode::InvalidController, "/controller/2".to_string()),
+                (FindingCode::InvalidController, "/controller/1".to_string()),
+            ]
+        );
+        assert_eq!(
+            findings(|doc| doc["verificationMethod"][0]["controller"] = json!("example")),
+            finding(
+                FindingCode::InvalidController,
+                "/verificationMethod/0/controller"
+            )
+        );
+        assert_eq!(
+            findings(|doc| {
+                doc["verificationMethod"][0]
+                    .as_object_mut()
+                    .unwrap()
+                    .remove("controller");
+            }),
+            finding(FindingCode::MissingProperty, "/verificationMethod/0")
+        );
+    }
+
+    #[test]
+    fn also_known_as() {
+        assert_eq!(
+            findings(|doc| doc["alsoKnownAs"] = json!(["example", 1])),
+            [
+                (
+                    FindingCode::InvalidAlsoKnownAs,
+                    "/alsoKnownAs/0".to_string()
+                ),
+                (
+                    FindingCode::InvalidAlsoKnownAs,
+                    "/alsoKnownAs/1".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            findings(|doc| doc["alsoKnownAs"] = json!("https://example.com")),
+            finding(FindingCode::InvalidPropertyType, "/alsoKnownAs")
+        );
+    }
+
+    #[test]
+    fn unique_verification_method_ids() {
+        assert_eq!(
+            findings(|doc| doc["assertionMethod"][0]["id"] = json!("#key-1")),
+            finding(
+                FindingCode::DuplicateVerificationMethodId,
+                "/assertionMethod/0/id"
+            )
+        );
+        assert_eq!(
+            findings(|doc| doc["verificationMethod"][0]["id"] = json!("key 1")),
+            [
+                (
+                    FindingCode::InvalidVerificationMethodId,
+                    "/verificationMethod/0/id".to_string()
+                ),
+                (
+                    FindingCode::UnresolvedReference,
+                    "/authentication/0".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            findings(|doc| doc["verificationMethod"]
+                .as_array_mut()
+                .unwrap()
+                .push(json!("#key-1"))),
+            finding(
+                FindingCode::InvalidVerificationMethodId,
+                "/verificationMethod/1"
+            )
+        );
+    }
+
+    #[test]
+    fn unique_service_ids() {
+        assert_eq!(
+            findings(|doc| {
+                let service = doc["service"][0].clone();
+                doc["service"].as_array_mut().unwrap().push(service);
+            }),
+            finding(FindingCode::DuplicateServiceId, "/service/1/id")
+        );
+        assert_eq!(
+            findings(|doc| doc["service"][0]["id"] = json!("linked domain")),
+            finding(FindingCode::InvalidServiceId, "/service/0/id")
+        );
+    }
+
+    #[test]
+    fn relative_references_resolve() {
+        assert_eq!(
+            findings(|doc| doc["authentication"][0] = json!("#key-3")),
+            finding(FindingCode::UnresolvedReference, "/authentication/0")
+        );
+        assert_eq!(
+            findings(|doc| doc["authentication"][0] = json!("did:example:123#key-3")),
+            finding(FindingCode::UnresolvedReference, "/authentication/0")
+        );
+        // Absolute and relative ids refer to the same verification methods.
+        assert_eq!(
+            findings(|doc| doc["keyAgreement"] = json!(["#key-2", "did:example:123#key-1"])),
+            vec![]
+        );
+        assert_eq!(
+            findings(|doc| doc["authentication"][0] = json!("key 1")),
+            finding(
+                FindingCode::InvalidVerificationMethodId,
+                "/authentication/0"
+            )
+        );
+        assert_eq!(
+            findings(|doc| doc["authentication"] = json!("#key-1")),
+            finding(FindingCode::InvalidPropertyType, "/authentication")
+        );
+    }
+
+    #[test]
+    fn public_key_jwk_without_private_members() {
+        for member in PRIVATE_JWK_MEMBERS {
+            assert_eq!(
+                findings(|doc| doc["verificationMethod"][0]["publicKeyJwk"][member] = json!("AA")),
+                finding(
+                    FindingCode::PrivateKeyMaterial,
+                    "/verificationMethod/0/publicKeyJwk"
+                ),
+                "{}",
+                member
+            );
+        }
+        assert_eq!(
+            findings(|doc| {
+                doc["verificationMethod"][0]["publicKeyJwk"]
+                    .as_object_mut()
+                    .unwrap()
+                    .remove("kty");
+            }),
+            finding(
+                FindingCode::InvalidPublicKeyJwk,
+                "/verificationMethod/0/publicKeyJwk"
+            )
+        );
+        assert_eq!(
+            findings(|doc| doc["verificationMethod"][0]["publicKeyJwk"] = json!("key")),
+            finding(
+                FindingCode::InvalidPublicKeyJwk,
+                "/verificationMethod/0/publicKeyJwk"
+            )
+        );
+    }
+
+    #[test]
+    fn known_verification_method_types() {
+        let findings = {
+            let mut doc = valid_document();
+            doc["verificationMethod"][0]["type"] = json!("UnknownKey2000");
+            validate_value(&doc)
+        };
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(findings[0].code, FindingCode::UnknownVerificationMethodType);
+        assert_eq!(findings[0].path, "/verificationMethod/0/type");
+    }
+
+    #[test]
+    fn service_type() {
+        assert_eq!(
+            findings(|doc| {
+                doc["service"][0].as_object_mut().unwrap().remove("type");
+            }),
+            finding(FindingCode::MissingProperty, "/service/0")
+        );
+        assert_eq!(
+            findings(|doc| doc["service"][0]["type"] = json!([])),
+            finding(FindingCode::InvalidServiceType, "/service/0/type")
+        );
+        assert_eq!(
+            findings(|doc| doc["service"][0]["type"] = json!(["LinkedDomains", 1])),
+            finding(FindingCode::InvalidServiceType, "/service/0/type/1")
+        );
+    }
+
+    #[test]
+    fn service_endpoint_shapes() {
+        let endpoint_findings =
+            |endpoint: Value| findings(|doc| doc["service"][0]["serviceEndpoint"] = endpoint);
+        assert_eq!(endpoint_findings(json!("https://example.com")), vec![]);
+        assert_eq!(
+            endpoint_findings(json!({"origins": ["https://example.com"]})),
+            vec![]
+        );
+        // Endpoints of known service types must have the structure defined for the type.
+        assert_eq!(
+            endpoint_findings(json!({"uri": "https://example.com"})),
+            finding(
+                FindingCode::InvalidServiceEndpoint,
+                "/service/0/serviceEndpoint"
+            )
+        );
+        assert_eq!(
+            endpoint_findings(json!("https://example.com/path")),
+            finding(
+                FindingCode::InvalidServiceEndpoint,
+                "/service/0/serviceEndpoint"
+            )
+        );
+        // Unknown service types only need URIs or maps.
+        assert_eq!(
+            findings(|doc| {
+                doc["service"][0]["type"] = json!("ExampleService");
+                doc["service"][0]["serviceEndpoint"] = json!({"uri": "https://example.com"});
+            }),
+            vec![]
+        );
+        assert_eq!(
+            endpoint_findings(json!("example.com")),
+            finding(
+                FindingCode::InvalidServiceEndpoint,
+                "/service/0/serviceEndpoint"
+            )
+        );
+        assert_eq!(
+            endpoint_findings(json!([])),
+            finding(
+                FindingCode::InvalidServiceEndpoint,
+                "/service/0/serviceEndpoint"
+            )
+        );
+        assert_eq!(
+            endpoint_findings(json!(["https://example.com", ["https://example.org"], 1])),
+            [
+                (
+                    FindingCode::InvalidServiceEndpoint,
+                    "/service/0/serviceEndpoint/1".to_string()
+                ),
+                (
+                    FindingCode::InvalidServiceEndpoint,
+                    "/service/0/serviceEndpoint/2".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            findings(|doc| {
+                doc["service"][0]
+                    .as_object_mut()
+                    .unwrap()
+                    .remove("serviceEndpoint");
+            }),
+            finding(FindingCode::InvalidServiceEndpoint, "/service/0")
+        );
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let findings = validate_value(&json!({
+            "id": "example",
+            "verificationMethod": [{"id": "#key-1", "publicKeyJwk": {"kty": "EC", "d": "AA"}}],
+            "authentication": ["#key-2"],
+            "service": [{"id": "#service", "serviceEndpoint": 1}],
+        }));
+        let codes: Vec<FindingCode> = findings.iter().map(|finding| finding.code).collect();
+        assert_eq!(
+            codes,
+            [
+                FindingCode::InvalidId,
+                FindingCode::MissingProperty,
+                FindingCode::MissingProperty,
+                FindingCode::PrivateKeyMaterial,
+                FindingCode::UnresolvedReference,
+                FindingCode::MissingProperty,
+                FindingCode::InvalidServiceEndpoint,
+            ]
+        );
+    }
+}
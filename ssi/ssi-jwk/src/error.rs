@@ -5,4 +5,13 @@ pub enum Error {
     /// Missing elliptic curve point in JWK
     #[error("Missing elliptic curve point in JWK")]
     MissingPoint,
+    /// Missing private key parameter in JWK
+    #[error("Missing private key parameter in JWK")]
+    MissingPrivateKey,
+    /// Curve not supported for the operation
+    #[error("Curve not supported: {0}")]
+    CurveNotImplemented(String),
+    /// Key parameters do not form a valid key
+    #[error("Invalid key parameters")]
+    InvalidKey,
 }
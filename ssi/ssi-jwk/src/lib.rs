@@ -6,25 +6,25 @@ mod error;
 pub use error::Error;
 use zeroize::Zeroize;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Zeroize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Zeroize)]
 #[serde(try_from = "String")]
 #[serde(into = "Base64urlUIntString")]
 pub struct Base64urlUInt(Vec<u8>);
 type Base64urlUIntString = String;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct JWK {
     #[serde(flatten)]
     pub params: Params,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "kty")]
 pub enum Params {
     EC(ECParams),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Zeroize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Zeroize)]
 pub struct ECParams {
     // Parameters for Elliptic Curve Public Keys
     #[serde(rename = "crv")]
@@ -63,6 +63,11 @@ impl JWK {
         })
     }
 
+    /// Whether two JWKs have the same public key, ignoring private key parameters.
+    pub fn equals_public(&self, other: &JWK) -> bool {
+        self.params.to_public() == other.params.to_public()
+    }
+
     pub fn to_public(&self) -> Self {
         let mut key = self.clone();
         key.params = key.params.to_public();
@@ -127,3 +132,32 @@ impl TryFrom<&k256::PublicKey> for ECParams {
         })
     }
 }
+
+#[cfg(feature = "secp256k1")]
+impl TryFrom<&ECParams> for k256::SecretKey {
+    type Error = Error;
+    fn try_from(params: &ECParams) -> Result<Self, Self::Error> {
+        if params.curve != "secp256k1" {
+            return Err(Error::CurveNotImplemented(params.curve.clone()));
+        }
+        let private_key = params
+            .ecc_private_key
+            .as_ref()
+            .ok_or(Error::MissingPrivateKey)?;
+        k256::SecretKey::from_slice(&private_key.0).map_err(|_| Error::InvalidKey)
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+impl TryFrom<&ECParams> for k256::PublicKey {
+    type Error = Error;
+    fn try_from(params: &ECParams) -> Result<Self, Self::Error> {
+        if params.curve != "secp256k1" {
+            return Err(Error::CurveNotImplemented(params.curve.clone()));
+        }
+        let mut pk_bytes = vec![0x04];
+        pk_bytes.extend_from_slice(&params.x_coordinate.0);
+        pk_bytes.extend_from_slice(&params.y_coordinate.0);
+        k256::PublicKey::from_sec1_bytes(&pk_bytes).map_err(|_| Error::InvalidKey)
+    }
+}
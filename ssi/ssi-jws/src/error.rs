@@ -0,0 +1,26 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Error from the key
+    #[error(transparent)]
+    JWK(#[from] ssi_jwk::Error),
+    /// Unable to serialize or deserialize header
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Invalid base64url encoding
+    #[error(transparent)]
+    Base64(#[from] base64::DecodeError),
+    /// Not a JWS in compact serialization
+    #[error("Invalid JWS")]
+    InvalidJWS,
+    /// Signature does not verify
+    #[error("Invalid signature")]
+    InvalidSignature,
+    /// Curve not supported for signing
+    #[error("Unsupported curve: {0}")]
+    UnsupportedCurve(String),
+    /// `crit` header parameter is not supported
+    #[error("Unsupported critical header parameter")]
+    UnsupportedCriticalHeader,
+}
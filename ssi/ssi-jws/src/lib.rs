@@ -0,0 +1,176 @@
+//! # JSON Web Signature (JWS)
+//!
+//! As specified in [RFC 7515](https://datatracker.ietf.org/doc/html/rfc7515), for the algorithms
+//! supported by [ssi_jwk].
+
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+use ssi_jwk::{Params, JWK};
+
+mod error;
+pub use error::Error;
+
+/// JWS signature algorithm
+///
+/// [RFC 7518 §3.1](https://datatracker.ietf.org/doc/html/rfc7518#section-3.1)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// ECDSA using secp256k1 and SHA-256
+    ///
+    /// [RFC 8812 §3.2](https://datatracker.ietf.org/doc/html/rfc8812#section-3.2)
+    ES256K,
+}
+
+/// JOSE Header
+///
+/// [RFC 7515 §4](https://datatracker.ietf.org/doc/html/rfc7515#section-4)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Header {
+    #[serde(rename = "alg")]
+    pub algorithm: Algorithm,
+
+    #[serde(rename = "kid")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+
+    #[serde(rename = "typ")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwk: Option<JWK>,
+
+    /// [RFC 7797](https://datatracker.ietf.org/doc/html/rfc7797) Unencoded Payload Option
+    #[serde(rename = "b64")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base64urlencode_payload: Option<bool>,
+
+    #[serde(rename = "crit")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critical: Option<Vec<String>>,
+}
+
+impl Header {
+    /// Construct a header with only the algorithm set.
+    pub fn new(algorithm: Algorithm) -> Self {
+        Self {
+            algorithm,
+            key_id: None,
+            type_: None,
+            jwk: None,
+            base64urlencode_payload: None,
+            critical: None,
+        }
+    }
+}
+
+/// Get the default signing algorithm for a JWK.
+pub fn algorithm_for_jwk(jwk: &JWK) -> Result<Algorithm, Error> {
+    match &jwk.params {
+        Params::EC(params) if params.curve == "secp256k1" => Ok(Algorithm::ES256K),
+        Params::EC(params) => Err(Error::UnsupportedCurve(params.curve.clone())),
+    }
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+fn base64_decode(data: &str) -> Result<Vec<u8>, Error> {
+    base64::decode_config(data, base64::URL_SAFE_NO_PAD).map_err(Error::Base64)
+}
+
+/// Sign data with a private key.
+pub fn sign_bytes(algorithm: Algorithm, data: &[u8], key: &JWK) -> Result<Vec<u8>, Error> {
+    match (algorithm, &key.params) {
+        (Algorithm::ES256K, Params::EC(params)) => {
+            use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+            let secret_key = k256::SecretKey::try_from(params)?;
+            let signing_key = SigningKey::from(secret_key);
+            let signature: Signature = signing_key.sign(data);
+            Ok(signature.to_bytes().to_vec())
+        }
+    }
+}
+
+/// Verify a signature over data with a public key.
+pub fn verify_bytes(
+    algorithm: Algorithm,
+    data: &[u8],
+    key: &JWK,
+    signature: &[u8],
+) -> Result<(), Error> {
+    match (algorithm, &key.params) {
+        (Algorithm::ES256K, Params::EC(params)) => {
+            use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+            let public_key = k256::PublicKey::try_from(params)?;
+            let verifying_key = VerifyingKey::from(public_key);
+            let signature =
+                Signature::from_slice(signature).map_err(|_| Error::InvalidSignature)?;
+            // Accept only normalized (low-S) signatures, as produced by [sign_bytes].
+            if signature.normalize_s().is_some() {
+                return Err(Error::InvalidSignature);
+            }
+            verifying_key
+                .verify(data, &signature)
+                .map_err(|_| Error::InvalidSignature)
+        }
+    }
+}
+
+/// Sign a payload with a custom header, producing a JWS in [Compact
+/// Serialization](https://datatracker.ietf.org/doc/html/rfc7515#section-7.1).
+pub fn encode_sign_custom_header(
+    payload: &str,
+    key: &JWK,
+    header: &Header,
+) -> Result<String, Error> {
+    let header_b64 = base64_encode(&serde_json::to_vec(header)?);
+    let payload_b64 = base64_encode(payload.as_bytes());
+    let signing_input = format!("{header_b64}.{payload_b64}");
+    let signature = sign_bytes(header.algorithm, signing_input.as_bytes(), key)?;
+    Ok(format!("{signing_input}.{}", base64_encode(&signature)))
+}
+
+/// Sign a payload, producing a JWS in [Compact
+/// Serialization](https://datatracker.ietf.org/doc/html/rfc7515#section-7.1).
+pub fn encode_sign(algorithm: Algorithm, payload: &str, key: &JWK) -> Result<String, Error> {
+    encode_sign_custom_header(payload, key, &Header::new(algorithm))
+}
+
+/// Split a compact JWS into its header, payload and signature parts.
+pub fn split_jws(jws: &str) -> Result<(&str, &str, &str), Error> {
+    let mut parts = jws.splitn(3, '.');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(header), Some(payload), Some(signature)) if !signature.contains('.') => {
+            Ok((header, payload, signature))
+        }
+        _ => Err(Error::InvalidJWS),
+    }
+}
+
+/// Decode a compact JWS without verifying its signature.
+pub fn decode_unverified(jws: &str) -> Result<(Header, Vec<u8>), Error> {
+    let (header_b64, payload_b64, _) = split_jws(jws)?;
+    let header: Header = serde_json::from_slice(&base64_decode(header_b64)?)?;
+    let payload = base64_decode(payload_b64)?;
+    Ok((header, payload))
+}
+
+/// Decode a compact JWS and verify its signature with a public key.
+///
+/// Returns the header and the payload.
+pub fn decode_verify(jws: &str, key: &JWK) -> Result<(Header, Vec<u8>), Error> {
+    let (header_b64, payload_b64, signature_b64) = split_jws(jws)?;
+    let header: Header = serde_json::from_slice(&base64_decode(header_b64)?)?;
+    if header.critical.is_some() {
+        return Err(Error::UnsupportedCriticalHeader);
+    }
+    let payload = base64_decode(payload_b64)?;
+    let signature = base64_decode(signature_b64)?;
+    let signing_input = format!("{header_b64}.{payload_b64}");
+    verify_bytes(header.algorithm, signing_input.as_bytes(), key, &signature)?;
+    Ok((header, payload))
+}